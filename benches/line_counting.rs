@@ -1,8 +1,8 @@
 //! Benchmarks for line counting
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
 use maram::stats::count_lines;
-use std::fs::File;
 use std::io::Write;
 use tempfile::NamedTempFile;
 
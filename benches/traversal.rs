@@ -1,6 +1,7 @@
 //! Benchmarks for filesystem traversal
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
 use maram::{FilterOptions, Walker};
 use std::fs::{self, File};
 use std::io::Write;
@@ -79,8 +80,10 @@ fn benchmark_filtered_traversal(c: &mut Criterion) {
     
     c.bench_function("traverse_filtered", |b| {
         b.iter(|| {
-            let mut filter_opts = FilterOptions::default();
-            filter_opts.include = Some(regex::Regex::new(r"file_1").unwrap());
+            let filter_opts = FilterOptions {
+                include: Some(regex::Regex::new(r"file_1").unwrap()),
+                ..Default::default()
+            };
             let mut walker = Walker::new(black_box(path), filter_opts, 1).unwrap();
             let _ = walker.walk().unwrap();
         });
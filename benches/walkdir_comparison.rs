@@ -1,6 +1,7 @@
 //! Benchmarks comparing maram's custom walker with walkdir
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
 use maram::{FilterOptions, Walker};
 use std::fs::{self, File};
 use std::io::Write;
@@ -99,8 +100,10 @@ fn benchmark_with_filtering(c: &mut Criterion) {
     // Benchmark maram with filtering
     group.bench_function("maram_filtered", |b| {
         b.iter(|| {
-            let mut filter_opts = FilterOptions::default();
-            filter_opts.include = Some(regex::Regex::new(r"file_1").unwrap());
+            let filter_opts = FilterOptions {
+                include: Some(regex::Regex::new(r"file_1").unwrap()),
+                ..Default::default()
+            };
             let mut walker = Walker::new(black_box(path), filter_opts, 1).unwrap();
             let entries = walker.walk().unwrap();
             black_box(entries.len());
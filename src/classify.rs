@@ -0,0 +1,516 @@
+//! File type classification
+//!
+//! This module assigns every file a category (its "kind", such as `Images`
+//! or `Code`) and a MIME type. Classification first consults user-defined
+//! categories from the config (globs, then extensions), then a built-in
+//! extension table, and finally sniffs the first bytes of the file for
+//! well-known magic numbers so that extensionless binaries, images and
+//! archives are recognised too.
+
+use crate::config::CategoryConfig;
+use crate::stats::is_binary_file;
+use crate::{Error, Result, TreeEntry};
+use colored::Color;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Category used for files that match nothing else
+pub const OTHER: &str = "Other";
+
+/// Built-in extension table: (extension, category, MIME type)
+const EXTENSIONS: &[(&str, &str, &str)] = &[
+    // Images
+    ("png", "Images", "image/png"),
+    ("jpg", "Images", "image/jpeg"),
+    ("jpeg", "Images", "image/jpeg"),
+    ("gif", "Images", "image/gif"),
+    ("bmp", "Images", "image/bmp"),
+    ("svg", "Images", "image/svg+xml"),
+    ("webp", "Images", "image/webp"),
+    ("ico", "Images", "image/vnd.microsoft.icon"),
+    ("tif", "Images", "image/tiff"),
+    ("tiff", "Images", "image/tiff"),
+    ("avif", "Images", "image/avif"),
+    ("heic", "Images", "image/heic"),
+    ("psd", "Images", "image/vnd.adobe.photoshop"),
+    // Videos
+    ("mp4", "Videos", "video/mp4"),
+    ("m4v", "Videos", "video/mp4"),
+    ("avi", "Videos", "video/x-msvideo"),
+    ("mkv", "Videos", "video/x-matroska"),
+    ("mov", "Videos", "video/quicktime"),
+    ("wmv", "Videos", "video/x-ms-wmv"),
+    ("webm", "Videos", "video/webm"),
+    ("flv", "Videos", "video/x-flv"),
+    // Audio
+    ("mp3", "Audio", "audio/mpeg"),
+    ("wav", "Audio", "audio/wav"),
+    ("flac", "Audio", "audio/flac"),
+    ("aac", "Audio", "audio/aac"),
+    ("ogg", "Audio", "audio/ogg"),
+    ("opus", "Audio", "audio/opus"),
+    ("m4a", "Audio", "audio/mp4"),
+    // Archives
+    ("zip", "Archives", "application/zip"),
+    ("tar", "Archives", "application/x-tar"),
+    ("gz", "Archives", "application/gzip"),
+    ("tgz", "Archives", "application/gzip"),
+    ("bz2", "Archives", "application/x-bzip2"),
+    ("xz", "Archives", "application/x-xz"),
+    ("zst", "Archives", "application/zstd"),
+    ("7z", "Archives", "application/x-7z-compressed"),
+    ("rar", "Archives", "application/vnd.rar"),
+    ("jar", "Archives", "application/java-archive"),
+    ("deb", "Archives", "application/vnd.debian.binary-package"),
+    ("rpm", "Archives", "application/x-rpm"),
+    ("crate", "Archives", "application/gzip"),
+    // Documents
+    ("pdf", "Documents", "application/pdf"),
+    ("doc", "Documents", "application/msword"),
+    ("docx", "Documents", "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+    ("xls", "Documents", "application/vnd.ms-excel"),
+    ("xlsx", "Documents", "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+    ("ppt", "Documents", "application/vnd.ms-powerpoint"),
+    ("pptx", "Documents", "application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+    ("odt", "Documents", "application/vnd.oasis.opendocument.text"),
+    ("epub", "Documents", "application/epub+zip"),
+    // Code
+    ("rs", "Code", "text/x-rust"),
+    ("js", "Code", "text/javascript"),
+    ("mjs", "Code", "text/javascript"),
+    ("jsx", "Code", "text/javascript"),
+    ("ts", "Code", "text/x-typescript"),
+    ("tsx", "Code", "text/x-typescript"),
+    ("py", "Code", "text/x-python"),
+    ("go", "Code", "text/x-go"),
+    ("c", "Code", "text/x-c"),
+    ("h", "Code", "text/x-c"),
+    ("cpp", "Code", "text/x-c++"),
+    ("cc", "Code", "text/x-c++"),
+    ("hpp", "Code", "text/x-c++"),
+    ("java", "Code", "text/x-java"),
+    ("kt", "Code", "text/x-kotlin"),
+    ("swift", "Code", "text/x-swift"),
+    ("cs", "Code", "text/x-csharp"),
+    ("rb", "Code", "text/x-ruby"),
+    ("php", "Code", "text/x-php"),
+    ("sh", "Code", "text/x-shellscript"),
+    ("bash", "Code", "text/x-shellscript"),
+    ("zsh", "Code", "text/x-shellscript"),
+    ("lua", "Code", "text/x-lua"),
+    ("pl", "Code", "text/x-perl"),
+    ("hs", "Code", "text/x-haskell"),
+    ("scala", "Code", "text/x-scala"),
+    ("zig", "Code", "text/x-zig"),
+    ("sql", "Code", "application/sql"),
+    ("html", "Code", "text/html"),
+    ("htm", "Code", "text/html"),
+    ("css", "Code", "text/css"),
+    ("scss", "Code", "text/x-scss"),
+    ("vue", "Code", "text/x-vue"),
+    // Config and data
+    ("toml", "Config", "application/toml"),
+    ("yaml", "Config", "application/yaml"),
+    ("yml", "Config", "application/yaml"),
+    ("json", "Config", "application/json"),
+    ("ini", "Config", "text/plain"),
+    ("cfg", "Config", "text/plain"),
+    ("conf", "Config", "text/plain"),
+    ("xml", "Config", "application/xml"),
+    ("lock", "Config", "text/plain"),
+    ("csv", "Data", "text/csv"),
+    ("tsv", "Data", "text/tab-separated-values"),
+    ("parquet", "Data", "application/vnd.apache.parquet"),
+    // Databases
+    ("db", "Databases", "application/vnd.sqlite3"),
+    ("sqlite", "Databases", "application/vnd.sqlite3"),
+    ("sqlite3", "Databases", "application/vnd.sqlite3"),
+    // Executables and libraries
+    ("exe", "Executables", "application/vnd.microsoft.portable-executable"),
+    ("dll", "Executables", "application/vnd.microsoft.portable-executable"),
+    ("so", "Executables", "application/x-sharedlib"),
+    ("dylib", "Executables", "application/x-mach-binary"),
+    ("o", "Executables", "application/x-object"),
+    ("a", "Executables", "application/x-archive"),
+    ("rlib", "Executables", "application/x-archive"),
+    ("wasm", "Executables", "application/wasm"),
+    // Fonts
+    ("ttf", "Fonts", "font/ttf"),
+    ("otf", "Fonts", "font/otf"),
+    ("woff", "Fonts", "font/woff"),
+    ("woff2", "Fonts", "font/woff2"),
+    // Text
+    ("txt", "Text", "text/plain"),
+    ("md", "Text", "text/markdown"),
+    ("rst", "Text", "text/x-rst"),
+    ("log", "Text", "text/plain"),
+];
+
+/// Magic number signatures: (offset, bytes, category, MIME type)
+const MAGIC: &[(usize, &[u8], &str, &str)] = &[
+    (0, b"\x7FELF", "Executables", "application/x-executable"),
+    (0, b"\xFE\xED\xFA\xCE", "Executables", "application/x-mach-binary"),
+    (0, b"\xFE\xED\xFA\xCF", "Executables", "application/x-mach-binary"),
+    (0, b"\xCE\xFA\xED\xFE", "Executables", "application/x-mach-binary"),
+    (0, b"\xCF\xFA\xED\xFE", "Executables", "application/x-mach-binary"),
+    (0, b"\xCA\xFE\xBA\xBE", "Executables", "application/x-mach-binary"),
+    (0, b"\x89PNG\r\n\x1A\n", "Images", "image/png"),
+    (0, b"\xFF\xD8\xFF", "Images", "image/jpeg"),
+    (0, b"GIF87a", "Images", "image/gif"),
+    (0, b"GIF89a", "Images", "image/gif"),
+    (0, b"\x1F\x8B", "Archives", "application/gzip"),
+    (0, b"\x28\xB5\x2F\xFD", "Archives", "application/zstd"),
+    (0, b"PK\x03\x04", "Archives", "application/zip"),
+    (0, b"PK\x05\x06", "Archives", "application/zip"),
+    (0, b"%PDF-", "Documents", "application/pdf"),
+    (0, b"SQLite format 3\0", "Databases", "application/vnd.sqlite3"),
+    (0, b"#!", "Code", "text/x-script"),
+];
+
+/// Number of bytes read from a file for magic number sniffing
+const SNIFF_LEN: usize = 16;
+
+/// Largest PE header offset accepted after an `MZ` stub
+const MAX_PE_OFFSET: u32 = 0x10000;
+
+/// Category and MIME type of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Classification {
+    /// Category name (e.g. "Images", "Code")
+    pub kind: String,
+    /// MIME type
+    pub mime: String,
+}
+
+/// Classifies files using user categories, extensions and magic bytes
+#[derive(Debug, Clone, Default)]
+pub struct Classifier {
+    /// User-defined extension to category mapping (extensions lowercase)
+    user_extensions: HashMap<String, String>,
+    /// User-defined glob patterns
+    globs: GlobSet,
+    /// Category of each glob in `globs`, by index
+    glob_categories: Vec<String>,
+    /// Display colour per category
+    colors: HashMap<String, Color>,
+    /// Walk roots that globs are matched relative to
+    roots: Vec<PathBuf>,
+}
+
+impl Classifier {
+    /// Create a classifier extended with user categories from the config
+    ///
+    /// User categories take precedence over the built-in table, and globs
+    /// take precedence over extensions.
+    pub fn new(categories: &BTreeMap<String, CategoryConfig>) -> Result<Self> {
+        let mut user_extensions = HashMap::new();
+        let mut builder = GlobSetBuilder::new();
+        let mut glob_categories = Vec::new();
+        let mut colors = HashMap::new();
+        
+        for (category, def) in categories {
+            for ext in &def.extensions {
+                let ext = ext.trim_start_matches('.').to_lowercase();
+                user_extensions.insert(ext, category.clone());
+            }
+            
+            for pattern in &def.globs {
+                let glob = Glob::new(pattern).map_err(|e| {
+                    Error::config(format!("Invalid glob '{}' in category '{}': {}", pattern, category, e))
+                })?;
+                builder.add(glob);
+                glob_categories.push(category.clone());
+            }
+            
+            if let Some(color) = &def.color {
+                let color = color.parse::<Color>().map_err(|_| {
+                    Error::config(format!("Invalid colour '{}' in category '{}'", color, category))
+                })?;
+                colors.insert(category.clone(), color);
+            }
+        }
+        
+        let globs = builder.build()
+            .map_err(|e| Error::config(format!("Invalid category globs: {}", e)))?;
+        
+        Ok(Self { user_extensions, globs, glob_categories, colors, roots: Vec::new() })
+    }
+    
+    /// Classify a file by name, falling back to sniffing its contents
+    pub fn classify(&self, path: &Path) -> Classification {
+        let ext = lowercase_extension(path);
+        let builtin = ext.as_deref()
+            .and_then(|ext| EXTENSIONS.iter().find(|(e, _, _)| *e == ext));
+        
+        if let Some(category) = self.user_category(path, ext.as_deref()) {
+            let mime = match builtin {
+                Some((_, _, mime)) => mime.to_string(),
+                None => sniff(path).mime,
+            };
+            return Classification { kind: category.to_string(), mime };
+        }
+        
+        if let Some((_, category, mime)) = builtin {
+            return Classification { kind: category.to_string(), mime: mime.to_string() };
+        }
+        
+        sniff(path)
+    }
+    
+    /// Classify by name only, without touching the file
+    pub fn classify_name(&self, path: &Path) -> String {
+        let ext = lowercase_extension(path);
+        
+        if let Some(category) = self.user_category(path, ext.as_deref()) {
+            return category.to_string();
+        }
+        
+        ext.and_then(|ext| EXTENSIONS.iter().find(|(e, _, _)| *e == ext))
+            .map_or_else(|| OTHER.to_string(), |(_, category, _)| category.to_string())
+    }
+    
+    /// Colour configured for a category
+    pub fn color(&self, category: &str) -> Option<Color> {
+        self.colors.get(category).copied()
+    }
+    
+    /// Match globs relative to a walk root as well
+    ///
+    /// The canonical form is kept too, since the buffered walker reports
+    /// canonical paths while the streaming walker keeps the given prefix.
+    pub fn with_root(mut self, root: &Path) -> Self {
+        if let Ok(canonical) = root.canonicalize() {
+            self.roots.push(canonical);
+        }
+        self.roots.push(root.to_path_buf());
+        self
+    }
+    
+    /// Whether any category has a colour configured
+    pub fn has_colors(&self) -> bool {
+        !self.colors.is_empty()
+    }
+    
+    /// Find the user category matching a path by glob, then by extension
+    ///
+    /// Globs are tried against the file name, the path relative to the walk
+    /// root and finally the full path.
+    fn user_category(&self, path: &Path, ext: Option<&str>) -> Option<&str> {
+        if !self.globs.is_empty() {
+            let by_name = path.file_name().map(|name| self.globs.matches(name));
+            let relative = self.roots.iter().find_map(|root| path.strip_prefix(root).ok());
+            let index = by_name
+                .and_then(|m| m.first().copied())
+                .or_else(|| relative.and_then(|r| self.globs.matches(r).first().copied()))
+                .or_else(|| self.globs.matches(path).first().copied());
+            if let Some(index) = index {
+                return Some(&self.glob_categories[index]);
+            }
+        }
+        
+        ext.and_then(|ext| self.user_extensions.get(ext)).map(String::as_str)
+    }
+}
+
+/// Lowercase extension of a path
+fn lowercase_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase)
+}
+
+/// Classify a file by its magic number, or as text/binary
+fn sniff(path: &Path) -> Classification {
+    let mut header = [0u8; SNIFF_LEN];
+    let mut file = File::open(path).ok();
+    let len = file.as_mut()
+        .and_then(|file| file.read(&mut header).ok())
+        .unwrap_or(0);
+    let header = &header[..len];
+    
+    // "MZ" alone is too common in text; Windows executables point to a PE header
+    if header.starts_with(b"MZ") && file.as_mut().is_some_and(is_portable_executable) {
+        return Classification {
+            kind: "Executables".to_string(),
+            mime: "application/vnd.microsoft.portable-executable".to_string(),
+        };
+    }
+    
+    if let Some((_, _, category, mime)) = MAGIC.iter()
+        .find(|(offset, magic, _, _)| header.get(*offset..).is_some_and(|h| h.starts_with(magic)))
+    {
+        return Classification { kind: category.to_string(), mime: mime.to_string() };
+    }
+    
+    if len > 0 && !is_binary_file(path).unwrap_or(true) {
+        Classification { kind: "Text".to_string(), mime: "text/plain".to_string() }
+    } else {
+        Classification { kind: OTHER.to_string(), mime: "application/octet-stream".to_string() }
+    }
+}
+
+/// Check for the `PE\0\0` signature at the offset an MZ stub stores at 0x3C
+fn is_portable_executable(file: &mut File) -> bool {
+    let mut stub = [0u8; 0x40];
+    if file.seek(SeekFrom::Start(0)).is_err() || file.read_exact(&mut stub).is_err() {
+        return false;
+    }
+    
+    let offset = u32::from_le_bytes([stub[0x3C], stub[0x3D], stub[0x3E], stub[0x3F]]);
+    if offset > MAX_PE_OFFSET {
+        return false;
+    }
+    
+    let mut signature = [0u8; 4];
+    file.seek(SeekFrom::Start(u64::from(offset))).is_ok()
+        && file.read_exact(&mut signature).is_ok()
+        && signature == *b"PE\0\0"
+}
+
+/// Classify every file in the tree in parallel
+pub fn apply_classification(entries: &mut [TreeEntry], classifier: &Classifier) {
+    fn collect(entry: &TreeEntry, paths: &mut Vec<PathBuf>) {
+        if !entry.is_dir {
+            paths.push(entry.path.clone());
+        }
+        for child in &entry.children {
+            collect(child, paths);
+        }
+    }
+    
+    fn assign(entry: &mut TreeEntry, results: &mut HashMap<PathBuf, Classification>) {
+        if let Some(class) = results.remove(&entry.path) {
+            entry.kind = Some(class.kind);
+            entry.mime = Some(class.mime);
+        }
+        for child in &mut entry.children {
+            assign(child, results);
+        }
+    }
+    
+    let mut paths = Vec::new();
+    for entry in entries.iter() {
+        collect(entry, &mut paths);
+    }
+    
+    let mut results: HashMap<PathBuf, Classification> = paths
+        .into_par_iter()
+        .map(|path| {
+            let class = classifier.classify(&path);
+            (path, class)
+        })
+        .collect();
+    
+    for entry in entries.iter_mut() {
+        assign(entry, &mut results);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_classify_by_extension() {
+        let classifier = Classifier::default();
+        let class = classifier.classify(Path::new("Cargo.toml"));
+        assert_eq!(class.kind, "Config");
+        assert_eq!(class.mime, "application/toml");
+        
+        assert_eq!(classifier.classify_name(Path::new("photo.WEBP")), "Images");
+        assert_eq!(classifier.classify_name(Path::new("backup.tar.zst")), "Archives");
+        assert_eq!(classifier.classify_name(Path::new("README")), OTHER);
+    }
+    
+    #[test]
+    fn test_classify_by_magic_bytes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let classifier = Classifier::default();
+        
+        fs::write(root.join("program"), b"\x7FELF\x02\x01\x01\0\0\0").unwrap();
+        fs::write(root.join("picture"), b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR").unwrap();
+        fs::write(root.join("store"), b"SQLite format 3\0\x10\0").unwrap();
+        fs::write(root.join("notes"), "just some text\n").unwrap();
+        fs::write(root.join("blob"), [0u8, 1, 2, 3]).unwrap();
+        fs::write(root.join("mz-note"), "MZ was here, and wrote a long enough note to fill a DOS header.\n").unwrap();
+        let mut pe = vec![0u8; 0x84];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3C] = 0x80;
+        pe[0x80..].copy_from_slice(b"PE\0\0");
+        fs::write(root.join("setup"), pe).unwrap();
+        
+        let kind = |name: &str| classifier.classify(&root.join(name));
+        assert_eq!(kind("program").kind, "Executables");
+        assert_eq!(kind("picture").mime, "image/png");
+        assert_eq!(kind("store").kind, "Databases");
+        assert_eq!(kind("notes").kind, "Text");
+        assert_eq!(kind("blob").kind, OTHER);
+        assert_eq!(kind("mz-note").kind, "Text");
+        assert_eq!(kind("setup").mime, "application/vnd.microsoft.portable-executable");
+    }
+    
+    #[test]
+    fn test_user_categories() {
+        let mut categories = BTreeMap::new();
+        categories.insert("Code".to_string(), CategoryConfig {
+            extensions: vec![".toml".to_string(), "nim".to_string()],
+            ..Default::default()
+        });
+        categories.insert("Build".to_string(), CategoryConfig {
+            globs: vec!["Makefile".to_string(), "*.min.js".to_string(), "**/fixtures/**".to_string()],
+            color: Some("magenta".to_string()),
+            ..Default::default()
+        });
+        let classifier = Classifier::new(&categories).unwrap();
+        
+        let class = classifier.classify(Path::new("Cargo.toml"));
+        assert_eq!(class.kind, "Code");
+        assert_eq!(class.mime, "application/toml");
+        assert_eq!(classifier.classify_name(Path::new("main.nim")), "Code");
+        assert_eq!(classifier.classify_name(Path::new("/src/Makefile")), "Build");
+        assert_eq!(classifier.classify_name(Path::new("/web/app.min.js")), "Build");
+        assert_eq!(classifier.classify_name(Path::new("/tests/fixtures/a.rs")), "Build");
+        assert_eq!(classifier.classify_name(Path::new("/web/app.js")), "Code");
+        assert_eq!(classifier.color("Build"), Some(Color::Magenta));
+        assert_eq!(classifier.color("Code"), None);
+    }
+    
+    #[test]
+    fn test_invalid_categories() {
+        let mut categories = BTreeMap::new();
+        categories.insert("Bad".to_string(), CategoryConfig {
+            color: Some("not-a-colour".to_string()),
+            ..Default::default()
+        });
+        assert!(Classifier::new(&categories).is_err());
+        
+        categories.insert("Bad".to_string(), CategoryConfig {
+            globs: vec!["[".to_string()],
+            ..Default::default()
+        });
+        assert!(Classifier::new(&categories).is_err());
+    }
+    
+    #[test]
+    fn test_user_category_globs_relative_to_root() {
+        let mut categories = BTreeMap::new();
+        categories.insert("Docs".to_string(), CategoryConfig {
+            globs: vec!["LICENSE*".to_string(), "docs/**".to_string()],
+            ..Default::default()
+        });
+        let classifier = Classifier::new(&categories).unwrap().with_root(Path::new("./project"));
+        
+        assert_eq!(classifier.classify_name(Path::new("./project/docs/guide.md")), "Docs");
+        assert_eq!(classifier.classify_name(Path::new("./project/docs/img/a.png")), "Docs");
+        assert_eq!(classifier.classify_name(Path::new("./project/LICENSE-MIT")), "Docs");
+        assert_eq!(classifier.classify_name(Path::new("./project/src/docs.rs")), "Code");
+        assert_eq!(classifier.classify_name(Path::new("./project/src/docs/a.png")), "Images");
+    }
+}
@@ -4,8 +4,11 @@
 //! using the clap crate with derive macros for a clean, declarative API.
 
 use clap::Parser;
-use crate::filters::SortBy;
-use crate::formatter::{OutputFormat, DistributionType, DistributionFormat};
+use crate::filters::SortKey;
+use crate::config::Config;
+use crate::formatter::{Charset, OutputFormat, DistributionType, DistributionFormat, DistributionMetric, DistributionOutput};
+use crate::stats::TimeStyle;
+use crate::units::SizeUnits;
 
 /// maram - A modern, high-performance alternative to the Unix tree command
 ///
@@ -22,9 +25,13 @@ pub struct Args {
     
     // Display options
     /// Use Unicode characters for tree drawing (default: Unicode)
-    #[arg(long, short = 'u')]
+    #[arg(long)]
     pub unicode: bool,
     
+    /// Tree drawing style: ascii, unicode, rounded, heavy, double or compact
+    #[arg(long, value_enum, value_name = "STYLE")]
+    pub charset: Option<Charset>,
+    
     /// Enable colored output (auto-detected by default)
     #[arg(long)]
     pub color: bool,
@@ -33,10 +40,72 @@ pub struct Args {
     #[arg(long, conflicts_with = "color")]
     pub no_color: bool,
     
-    /// Show full absolute paths instead of relative
+    /// Show Nerd Font icons before names
+    #[arg(long)]
+    pub icons: bool,
+    
+    /// Long listing: permissions, owner, size, lines and mtime in aligned columns
+    #[arg(long, short = 'l')]
+    pub long: bool,
+    
+    /// Show permissions, like `tree -p`
+    #[arg(long, short = 'p')]
+    pub permissions: bool,
+    
+    /// Show the owner, like `tree -u`
+    #[arg(long, short = 'u')]
+    pub owner: bool,
+    
+    /// Show the group, like `tree -g`
+    #[arg(long, short = 'g')]
+    pub group: bool,
+    
+    /// Show inode numbers
+    #[arg(long)]
+    pub inodes: bool,
+    
+    /// Show the device ID of the containing filesystem
+    #[arg(long)]
+    pub device: bool,
+    
+    /// Show the last modification time, like `tree -D`
+    #[arg(long, short = 'D')]
+    pub date: bool,
+    
+    /// Timestamp format for -D, --long, CSV and JSON: iso, long-iso, relative or +FORMAT
+    #[arg(long, value_name = "STYLE")]
+    pub time_style: Option<TimeStyle>,
+    
+    /// Show each entry's path from the root, like `tree -f`
     #[arg(long, short = 'f')]
     pub full_path: bool,
     
+    /// Show canonical absolute paths
+    #[arg(long)]
+    pub absolute: bool,
+    
+    /// Name shown for the root instead of the path as given
+    #[arg(long, value_name = "LABEL")]
+    pub root_label: Option<String>,
+    
+    /// Show chains of directories that only hold one subdirectory on one line
+    #[arg(long)]
+    pub compact_dirs: bool,
+    
+    /// Fit the tree in LINES lines (default: terminal height), opening the biggest directories first
+    ///
+    /// Only applies to --output=tree. The whole tree is still walked, since
+    /// directories are ranked by everything below them.
+    #[arg(long, value_name = "LINES", num_args = 0..=1, require_equals = true)]
+    pub fit: Option<Option<usize>>,
+    
+    /// Show matching directories as one line with their file count and size
+    ///
+    /// Takes comma-separated name globs; without them, collapses common
+    /// heavy directories such as node_modules, target, .venv and .git.
+    #[arg(long, value_name = "PATTERNS", num_args = 0..=1, require_equals = true, value_delimiter = ',')]
+    pub collapse: Option<Vec<String>>,
+    
     // Per-nested-path limits
     /// Maximum number of directories to show per directory
     #[arg(long, value_name = "N")]
@@ -46,6 +115,10 @@ pub struct Args {
     #[arg(long, value_name = "N")]
     pub max_files: Option<usize>,
     
+    /// Don't open directories with more than N entries, like `tree --filelimit`
+    #[arg(long, value_name = "N")]
+    pub filelimit: Option<usize>,
+    
     // Inline details
     /// Show file sizes (default: on)
     #[arg(long, default_value = "true")]
@@ -59,6 +132,26 @@ pub struct Args {
     #[arg(long)]
     pub dir_sizes: bool,
     
+    /// Show code, comment and blank line counts per detected language
+    #[arg(long)]
+    pub code_stats: bool,
+    
+    /// Detect each file's category and MIME type, added to JSON output
+    #[arg(long)]
+    pub file_kinds: bool,
+    
+    /// Size units: iec (KiB, base 1024), si (kB, base 1000), bytes, or a fixed unit like MiB
+    #[arg(long, value_name = "UNITS")]
+    pub units: Option<SizeUnits>,
+    
+    /// Decimal places for sizes (default: 1)
+    #[arg(long, value_name = "N")]
+    pub precision: Option<usize>,
+    
+    /// Write sizes in CSV and JSON as formatted strings instead of byte counts
+    #[arg(long)]
+    pub human_sizes: bool,
+    
     /// Maximum file size for line counting (default: 1GB)
     #[arg(long, default_value = "1073741824", value_name = "BYTES")]
     pub max_file_size: u64,
@@ -80,11 +173,11 @@ pub struct Args {
     #[arg(long, conflicts_with = "only_dirs")]
     pub only_files: bool,
     
-    /// Minimum file size to include (e.g., 1MB, 500KB)
+    /// Minimum file size to include (e.g., 1MiB, 500kB)
     #[arg(long, value_name = "SIZE")]
     pub min_size: Option<String>,
     
-    /// Maximum file size to include (e.g., 10MB, 1GB)
+    /// Maximum file size to include (e.g., 10MiB, 1GB)
     #[arg(long, value_name = "SIZE")]
     pub max_size: Option<String>,
     
@@ -104,34 +197,95 @@ pub struct Args {
     #[arg(short, long)]
     pub all: bool,
     
+    /// Show only empty files and empty directories
+    #[arg(long)]
+    pub empty: bool,
+    
+    /// Hide directories that are empty after filtering
+    #[arg(long)]
+    pub prune: bool,
+    
+    /// Show only files in this category (e.g. Code, Images, Archives)
+    #[arg(long, value_name = "CATEGORY")]
+    pub category: Option<String>,
+    
     // Sorting options
-    /// Sort files by: name, size, time, ext, or lines
-    #[arg(long, value_enum, value_name = "FIELD")]
-    pub sort: Option<SortBy>,
+    /// Sort by comma-separated keys, each `field[:asc|:desc]`
+    ///
+    /// Fields: name, size, time, ext, lines, files, natural, version.
+    /// Directories sort by their recursive size, line and file counts.
+    #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+    pub sort: Vec<SortKey>,
     
     /// Reverse sort order
     #[arg(long, short = 'r')]
     pub reverse: bool,
     
+    /// Compare names case-insensitively when sorting
+    #[arg(long)]
+    pub ignore_case_sort: bool,
+    
+    /// List directories before files
+    #[arg(long, conflicts_with = "files_first")]
+    pub dirs_first: bool,
+    
+    /// List files before directories
+    #[arg(long, conflicts_with = "dirs_first")]
+    pub files_first: bool,
+    
     // Search options
     /// Search for files matching this regex pattern
     #[arg(long, value_name = "QUERY")]
     pub search: Option<String>,
     
-    /// Case-insensitive search
-    #[arg(long, short = 'i', requires = "search")]
+    /// Case-insensitive pattern matching
+    #[arg(long, short = 'i')]
     pub ignore_case: bool,
     
+    /// Show only files whose contents match this regex pattern
+    #[arg(long, value_name = "PATTERN")]
+    pub contains: Option<String>,
+    
+    /// Show the first matching line next to each file
+    #[arg(long, requires = "contains")]
+    pub first_match: bool,
+    
+    /// Fuzzy search file paths and show the best matches
+    #[arg(long, value_name = "QUERY")]
+    pub fuzzy: Option<String>,
+    
+    /// Number of fuzzy search results to show
+    #[arg(long, default_value = "20", value_name = "N", requires = "fuzzy")]
+    pub fuzzy_limit: usize,
+    
     // Summary options
     /// Show total size summary
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub total_size: bool,
     
+    /// List the N largest files with their share of the total size
+    #[arg(long, value_name = "N")]
+    pub largest: Option<usize>,
+    
+    /// List the N largest directories by recursive size
+    #[arg(long, value_name = "N")]
+    pub largest_dirs: Option<usize>,
+    
+    /// Don't print the tree, only the requested reports
+    #[arg(long)]
+    pub no_tree: bool,
+    
     // Size distribution
-    /// Show size distribution by: type, size, or ext
+    /// Show distribution by: type, size, ext, language, dir, age, owner or depth
     #[arg(long, value_enum, value_name = "TYPE")]
     pub dist: Option<DistributionType>,
     
+    /// What the distribution adds up: bytes (the default), files or lines
+    ///
+    /// With --dist=language a metric replaces the per-language line table.
+    #[arg(long, value_enum, value_name = "METRIC", requires = "dist")]
+    pub dist_metric: Option<DistributionMetric>,
+    
     /// Number of top items to show in distribution
     #[arg(long, default_value = "10", value_name = "N", requires = "dist")]
     pub top: usize,
@@ -140,6 +294,12 @@ pub struct Args {
     #[arg(long, value_enum, default_value = "chart", value_name = "FORMAT", requires = "dist")]
     pub format: DistributionFormat,
     
+    /// Distribution output: text (table or chart), json or csv
+    ///
+    /// json and csv print the distribution alone, so they need --no-tree.
+    #[arg(long, value_enum, default_value = "text", value_name = "FORMAT", requires = "dist")]
+    pub dist_output: DistributionOutput,
+    
     // Other options
     /// Maximum depth to traverse
     #[arg(short = 'L', long, value_name = "N")]
@@ -174,19 +334,60 @@ pub struct Args {
     pub ignore_errors: bool,
 }
 
+impl Args {
+    /// Tree drawing style: `--charset`, then `--unicode`, then the config
+    pub fn tree_charset(&self, config: &Config) -> Charset {
+        match self.charset {
+            Some(charset) => charset,
+            None if self.unicode => Charset::Unicode,
+            None => config.display.charset.unwrap_or(if config.display.unicode {
+                Charset::Unicode
+            } else {
+                Charset::Ascii
+            }),
+        }
+    }
+    
+    /// Whether any option needs permissions, ownership or inode details
+    pub fn needs_extended_metadata(&self) -> bool {
+        self.long || self.permissions || self.owner || self.group || self.inodes || self.device
+    }
+}
+
 impl Default for Args {
     fn default() -> Self {
         Self {
             path: ".".to_string(),
             unicode: true,
+            charset: None,
             color: false,
             no_color: false,
+            icons: false,
+            long: false,
+            permissions: false,
+            owner: false,
+            group: false,
+            inodes: false,
+            device: false,
+            date: false,
+            time_style: None,
             full_path: false,
+            absolute: false,
+            root_label: None,
+            compact_dirs: false,
+            fit: None,
+            collapse: None,
             max_dirs: None,
             max_files: None,
+            filelimit: None,
             show_size: true,
             show_lines: false,
             dir_sizes: false,
+            code_stats: false,
+            file_kinds: false,
+            units: None,
+            precision: None,
+            human_sizes: false,
             max_file_size: 1_073_741_824, // 1GB
             include: None,
             exclude: None,
@@ -198,14 +399,29 @@ impl Default for Args {
             older_than: None,
             gitignore: false,
             all: false,
-            sort: None,
+            empty: false,
+            prune: false,
+            category: None,
+            sort: Vec::new(),
             reverse: false,
+            ignore_case_sort: false,
+            dirs_first: false,
+            files_first: false,
             search: None,
             ignore_case: false,
+            contains: None,
+            first_match: false,
+            fuzzy: None,
+            fuzzy_limit: 20,
             total_size: false,
+            largest: None,
+            largest_dirs: None,
+            no_tree: false,
             dist: None,
+            dist_metric: None,
             top: 10,
             format: DistributionFormat::Chart,
+            dist_output: DistributionOutput::Text,
             depth: None,
             output: OutputFormat::Tree,
             threads: 0,
@@ -0,0 +1,430 @@
+//! Entry colouring compatible with `LS_COLORS`
+//!
+//! This module parses `LS_COLORS` (as produced by `dircolors`) plus the
+//! optional `[colors]` config section, and provides the single colouring
+//! function shared by the buffered printer and the streaming walker.
+//! `LS_COLORS` is parsed the way `ls` reads it: styles come from the file
+//! type (directory, symlink, orphan, setuid, sticky, ...) and then the
+//! extension. Category colours from the config are layered on top, taking
+//! precedence over the extension for regular files.
+
+use colored::{Color, Colorize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, Metadata};
+use std::ops::Range;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+/// Styles used when `LS_COLORS` does not set them
+///
+/// Directories, symlinks and executables keep maram's historic colours; the
+/// remaining types use the GNU `dircolors` defaults.
+const DEFAULT_STYLES: &[(&str, &str)] = &[
+    ("di", "1;34"),
+    ("ln", "36"),
+    ("ex", "32"),
+    ("or", "40;31;01"),
+    ("pi", "40;33"),
+    ("so", "01;35"),
+    ("bd", "40;33;01"),
+    ("cd", "40;33;01"),
+    ("su", "37;41"),
+    ("sg", "30;43"),
+    ("tw", "30;42"),
+    ("ow", "34;42"),
+    ("st", "37;44"),
+];
+
+/// File type of an entry, as distinguished by `LS_COLORS`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Indicator {
+    /// Regular file
+    #[default]
+    File,
+    /// Directory
+    Dir,
+    /// Directory that is sticky and other-writable (e.g. /tmp)
+    StickyOtherWritable,
+    /// Directory that is other-writable
+    OtherWritable,
+    /// Directory with the sticky bit
+    Sticky,
+    /// Symbolic link
+    Symlink,
+    /// Symbolic link whose target does not exist
+    Orphan,
+    /// Named pipe
+    Fifo,
+    /// Socket
+    Socket,
+    /// Block device
+    BlockDevice,
+    /// Character device
+    CharDevice,
+    /// Executable file
+    Executable,
+    /// File with the setuid bit
+    Setuid,
+    /// File with the setgid bit
+    Setgid,
+    /// Entry that could not be stat'ed
+    Missing,
+}
+
+impl Indicator {
+    /// Determine the indicator from `symlink_metadata` of a path
+    pub fn of(path: &Path, metadata: Option<&Metadata>) -> Self {
+        let Some(metadata) = metadata else {
+            return Self::Missing;
+        };
+        let file_type = metadata.file_type();
+        
+        if file_type.is_symlink() {
+            return if fs::metadata(path).is_ok() { Self::Symlink } else { Self::Orphan };
+        }
+        
+        #[cfg(unix)]
+        {
+            let mode = metadata.permissions().mode();
+            
+            if file_type.is_dir() {
+                return match (mode & 0o1000 != 0, mode & 0o002 != 0) {
+                    (true, true) => Self::StickyOtherWritable,
+                    (false, true) => Self::OtherWritable,
+                    (true, false) => Self::Sticky,
+                    (false, false) => Self::Dir,
+                };
+            }
+            if file_type.is_fifo() {
+                return Self::Fifo;
+            }
+            if file_type.is_socket() {
+                return Self::Socket;
+            }
+            if file_type.is_block_device() {
+                return Self::BlockDevice;
+            }
+            if file_type.is_char_device() {
+                return Self::CharDevice;
+            }
+            if mode & 0o4000 != 0 {
+                return Self::Setuid;
+            }
+            if mode & 0o2000 != 0 {
+                return Self::Setgid;
+            }
+            if mode & 0o111 != 0 {
+                return Self::Executable;
+            }
+        }
+        
+        #[cfg(not(unix))]
+        if file_type.is_dir() {
+            return Self::Dir;
+        }
+        
+        Self::File
+    }
+    
+    /// `LS_COLORS` keys to try, most specific first
+    fn keys(self) -> &'static [&'static str] {
+        match self {
+            Self::File => &[],
+            Self::Dir => &["di"],
+            Self::StickyOtherWritable => &["tw", "ow", "st", "di"],
+            Self::OtherWritable => &["ow", "di"],
+            Self::Sticky => &["st", "di"],
+            Self::Symlink => &["ln"],
+            Self::Orphan => &["or", "ln"],
+            Self::Fifo => &["pi"],
+            Self::Socket => &["so"],
+            Self::BlockDevice => &["bd"],
+            Self::CharDevice => &["cd"],
+            Self::Executable => &["ex"],
+            Self::Setuid => &["su", "ex"],
+            Self::Setgid => &["sg", "ex"],
+            Self::Missing => &["mi"],
+        }
+    }
+    
+    /// Whether this indicator falls back to extension and category styles
+    fn is_file(self) -> bool {
+        matches!(self, Self::File | Self::Executable | Self::Setuid | Self::Setgid)
+    }
+}
+
+/// Parsed `LS_COLORS` styles
+#[derive(Debug, Clone)]
+pub struct LsColors {
+    /// Styles by type key (`di`, `ln`, `ex`, ...)
+    types: HashMap<String, String>,
+    /// Styles for `*.ext` patterns, keyed by lowercase extension
+    extensions: HashMap<String, String>,
+    /// Styles for other `*suffix` patterns (lowercase suffix)
+    suffixes: Vec<(String, String)>,
+    /// `ln=target`: colour symlinks like the file they point to
+    link_target: bool,
+}
+
+impl Default for LsColors {
+    fn default() -> Self {
+        Self {
+            types: DEFAULT_STYLES.iter()
+                .map(|(key, style)| (key.to_string(), style.to_string()))
+                .collect(),
+            extensions: HashMap::new(),
+            suffixes: Vec::new(),
+            link_target: false,
+        }
+    }
+}
+
+impl LsColors {
+    /// Build styles from `LS_COLORS` and the `[colors]` config section
+    pub fn from_env_and_config(overrides: &BTreeMap<String, String>) -> Self {
+        let ls_colors = std::env::var("LS_COLORS").ok();
+        Self::new(ls_colors.as_deref(), overrides)
+    }
+    
+    /// Build styles from an `LS_COLORS` value and config overrides
+    ///
+    /// Malformed entries are ignored, as `ls` does. Config values may be SGR
+    /// codes (`01;34`) or names (`bold blue`).
+    pub fn new(ls_colors: Option<&str>, overrides: &BTreeMap<String, String>) -> Self {
+        let mut colors = Self::default();
+        
+        for entry in ls_colors.unwrap_or_default().split(':') {
+            if let Some((key, style)) = entry.split_once('=') {
+                colors.set(key, style.to_string());
+            }
+        }
+        
+        for (key, value) in overrides {
+            if let Some(style) = parse_style(value) {
+                colors.set(key, style);
+            }
+        }
+        
+        colors
+    }
+    
+    /// Set the style for a type key or `*suffix` pattern
+    fn set(&mut self, key: &str, style: String) {
+        if let Some(pattern) = key.strip_prefix('*') {
+            let pattern = pattern.to_lowercase();
+            match pattern.strip_prefix('.') {
+                Some(ext) if !ext.contains('.') => {
+                    self.extensions.insert(ext.to_string(), style);
+                }
+                _ => {
+                    self.suffixes.retain(|(suffix, _)| *suffix != pattern);
+                    self.suffixes.push((pattern, style));
+                }
+            }
+        } else if key == "ln" && style == "target" {
+            self.link_target = true;
+            self.types.remove("ln");
+        } else if !key.is_empty() {
+            self.types.insert(key.to_string(), style);
+        }
+    }
+    
+    /// Style for a type indicator, if one is set
+    fn type_style(&self, indicator: Indicator) -> Option<&str> {
+        indicator.keys().iter().find_map(|key| self.type_style_key(key))
+    }
+    
+    /// Style for a single type key
+    fn type_style_key(&self, key: &str) -> Option<&str> {
+        self.types.get(key).map(String::as_str)
+    }
+    
+    /// Style for a file name from the `*suffix` patterns
+    fn name_style(&self, name: &str) -> Option<&str> {
+        let lower = name.to_lowercase();
+        
+        // Longer suffixes like `*.tar.gz` win over plain extensions
+        self.suffixes.iter()
+            .filter(|(suffix, _)| lower.ends_with(suffix.as_str()))
+            .max_by_key(|(suffix, _)| suffix.len())
+            .map(|(_, style)| style.as_str())
+            .or_else(|| {
+                let (_, ext) = lower.rsplit_once('.')?;
+                self.extensions.get(ext).map(String::as_str)
+            })
+    }
+}
+
+/// What to paint a name with
+enum Style<'a> {
+    /// Raw SGR codes from `LS_COLORS`
+    Sgr(&'a str),
+    /// Category colour from the config
+    Category(Color),
+    /// No colour
+    Plain,
+}
+
+/// Colour an entry name, highlighting the given byte ranges
+///
+/// Shared by the buffered printer and the streaming walker so both paths
+/// colour entries identically. `indicator` comes from the entry's
+/// `symlink_metadata`, which the caller has already read.
+pub fn paint_name(
+    name: &str,
+    path: &Path,
+    mut indicator: Indicator,
+    colors: &LsColors,
+    category_color: Option<Color>,
+    highlights: &[Range<usize>],
+) -> String {
+    // `ln=target` colours a link like its target
+    if indicator == Indicator::Symlink && colors.link_target {
+        indicator = Indicator::of(path, fs::metadata(path).ok().as_ref());
+    }
+    
+    let style = match colors.type_style(indicator) {
+        Some(style) => Style::Sgr(style),
+        None if indicator.is_file() => match category_color {
+            Some(color) => Style::Category(color),
+            None => colors.name_style(name)
+                .or_else(|| colors.type_style_key("fi"))
+                .or_else(|| colors.type_style_key("no"))
+                .map_or(Style::Plain, Style::Sgr),
+        },
+        None => Style::Plain,
+    };
+    
+    let base = |text: &str| -> String {
+        match style {
+            Style::Sgr(sgr) if !is_reset(sgr) => format!("\x1b[{}m{}\x1b[0m", sgr, text),
+            Style::Category(color) => text.color(color).to_string(),
+            _ => text.to_string(),
+        }
+    };
+    
+    let mut painted = String::new();
+    let mut pos = 0;
+    
+    for range in highlights {
+        if range.start > pos {
+            painted.push_str(&base(&name[pos..range.start]));
+        }
+        painted.push_str(&name[range.clone()].bright_red().bold().to_string());
+        pos = range.end;
+    }
+    
+    if pos < name.len() {
+        painted.push_str(&base(&name[pos..]));
+    }
+    
+    painted
+}
+
+/// Whether an SGR sequence leaves the text unstyled
+fn is_reset(sgr: &str) -> bool {
+    sgr.chars().all(|c| c == '0')
+}
+
+/// Parse a config style: raw SGR codes or words like `bold bright blue`
+fn parse_style(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.chars().all(|c| c.is_ascii_digit() || c == ';') {
+        return Some(value.to_string());
+    }
+    
+    let mut codes = Vec::new();
+    let mut words: Vec<&str> = value.split_whitespace().collect();
+    
+    while let Some(code) = words.first().and_then(|word| match *word {
+        "bold" => Some("1"),
+        "dim" => Some("2"),
+        "italic" => Some("3"),
+        "underline" => Some("4"),
+        "blink" => Some("5"),
+        "reverse" => Some("7"),
+        _ => None,
+    }) {
+        codes.push(code.to_string());
+        words.remove(0);
+    }
+    
+    if !words.is_empty() {
+        let color: Color = words.join(" ").parse().ok()?;
+        codes.push(color.to_fg_str().into_owned());
+    }
+    
+    (!codes.is_empty()).then(|| codes.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_parse_ls_colors() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("*.rs".to_string(), "bold yellow".to_string());
+        overrides.insert("ex".to_string(), "01;35".to_string());
+        let colors = LsColors::new(Some("di=01;36:*.MD=33:*.tar.gz=31:*.gz=32:*README=4:bogus"), &overrides);
+        
+        assert_eq!(colors.type_style(Indicator::Dir), Some("01;36"));
+        assert_eq!(colors.type_style(Indicator::Setuid), Some("37;41"));
+        assert_eq!(colors.type_style(Indicator::Executable), Some("01;35"));
+        assert_eq!(colors.type_style(Indicator::Symlink), Some("36"));
+        assert_eq!(colors.name_style("notes.md"), Some("33"));
+        assert_eq!(colors.name_style("main.rs"), Some("1;33"));
+        assert_eq!(colors.name_style("src.tar.gz"), Some("31"));
+        assert_eq!(colors.name_style("log.gz"), Some("32"));
+        assert_eq!(colors.name_style("README"), Some("4"));
+        assert_eq!(colors.name_style("main.c"), None);
+    }
+    
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(parse_style("01;34").as_deref(), Some("01;34"));
+        assert_eq!(parse_style("bold bright blue").as_deref(), Some("1;94"));
+        assert_eq!(parse_style("underline").as_deref(), Some("4"));
+        assert_eq!(parse_style("sparkly"), None);
+    }
+    
+    #[test]
+    fn test_paint_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let file = root.join("notes.md");
+        fs::write(&file, "hi").unwrap();
+        
+        let colors = LsColors::new(Some("*.md=33"), &BTreeMap::new());
+        let painted = paint_name("notes.md", &file, Indicator::File, &colors, None, &[]);
+        assert_eq!(painted, "\x1b[33mnotes.md\x1b[0m");
+        
+        let painted = paint_name("dir", root, Indicator::Dir, &colors, None, &[]);
+        assert_eq!(painted, "\x1b[1;34mdir\x1b[0m");
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_indicator_of() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        
+        let file = root.join("tool");
+        fs::write(&file, "#!/bin/sh").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o4755)).unwrap();
+        let shared = root.join("shared");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+        let link = root.join("dangling");
+        std::os::unix::fs::symlink(root.join("nowhere"), &link).unwrap();
+        
+        let indicator = |path: &Path| Indicator::of(path, fs::symlink_metadata(path).ok().as_ref());
+        assert_eq!(indicator(&file), Indicator::Setuid);
+        assert_eq!(indicator(&shared), Indicator::StickyOtherWritable);
+        assert_eq!(indicator(&link), Indicator::Orphan);
+        assert_eq!(indicator(&root.join("missing")), Indicator::Missing);
+    }
+}
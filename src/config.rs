@@ -2,9 +2,12 @@
 //!
 //! This module manages loading and parsing configuration from ~/.maram.toml
 
+use crate::formatter::Charset;
+use crate::stats::TimeStyle;
+use crate::units::SizeUnits;
 use crate::{Error, Result};
-use crate::filters::SortBy;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -22,6 +25,36 @@ pub struct Config {
     /// Performance options
     #[serde(default)]
     pub performance: PerformanceConfig,
+    
+    /// User-defined file categories, keyed by category name
+    #[serde(default)]
+    pub categories: BTreeMap<String, CategoryConfig>,
+    
+    /// Colour overrides using `LS_COLORS` keys (e.g. `di = "01;34"`, `"*.rs" = "bold yellow"`)
+    #[serde(default)]
+    pub colors: BTreeMap<String, String>,
+    
+    /// Icon overrides for `--icons`
+    #[serde(default)]
+    pub icons: IconConfig,
+}
+
+/// A user-defined file category
+///
+/// Files matching a user category take precedence over the built-in table.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryConfig {
+    /// File extensions in this category (without the leading dot)
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    
+    /// Glob patterns matched against the file name and full path
+    #[serde(default)]
+    pub globs: Vec<String>,
+    
+    /// Colour for file names in this category (e.g. "magenta", "bright blue")
+    #[serde(default)]
+    pub color: Option<String>,
 }
 
 /// Display configuration
@@ -31,6 +64,10 @@ pub struct DisplayConfig {
     #[serde(default)]
     pub unicode: bool,
     
+    /// Tree drawing style (overrides `unicode`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charset: Option<Charset>,
+    
     /// Show file sizes by default
     #[serde(default = "default_true")]
     pub show_size: bool,
@@ -46,10 +83,26 @@ pub struct DisplayConfig {
     /// Show total size by default
     #[serde(default = "default_true")]
     pub total_size: bool,
+    
+    /// Show Nerd Font icons by default
+    #[serde(default)]
+    pub icons: bool,
+    
+    /// Timestamp format (iso, long-iso, relative or +FORMAT)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_style: Option<TimeStyle>,
+    
+    /// Size units (iec, si, bytes or a fixed unit like MiB)
+    #[serde(default)]
+    pub units: SizeUnits,
+    
+    /// Decimal places for sizes
+    #[serde(default = "default_precision")]
+    pub precision: usize,
 }
 
 /// Filter configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FilterConfig {
     /// Show hidden files by default
     #[serde(default)]
@@ -71,13 +124,55 @@ pub struct FilterConfig {
     #[serde(default)]
     pub max_files: Option<usize>,
     
-    /// Default sort field
+    /// Default sort keys (e.g. "name" or "size:desc,name")
     #[serde(default)]
-    pub sort_by: Option<SortBy>,
+    pub sort_by: Option<String>,
     
     /// Reverse sort by default
     #[serde(default)]
     pub reverse_sort: bool,
+    
+    /// Sort names case-insensitively by default
+    #[serde(default)]
+    pub ignore_case_sort: bool,
+    
+    /// List directories before files
+    #[serde(default)]
+    pub dirs_first: bool,
+    
+    /// List files before directories
+    #[serde(default)]
+    pub files_first: bool,
+    
+    /// Default file limit: directories with more entries are not opened
+    #[serde(default)]
+    pub filelimit: Option<usize>,
+    
+    /// Directory name globs to collapse into a summary line
+    #[serde(default)]
+    pub collapse: Vec<String>,
+}
+
+/// Icon configuration
+///
+/// Icons are Nerd Font glyphs; keys are matched case-insensitively.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IconConfig {
+    /// Spaces printed after each icon
+    #[serde(default = "default_icon_spacing")]
+    pub spacing: usize,
+    
+    /// Icons by directory name (e.g. `".git" = "\ue5fb"`)
+    #[serde(default)]
+    pub dirs: BTreeMap<String, String>,
+    
+    /// Icons by file name (e.g. `Justfile`)
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    
+    /// Icons by extension, without the dot
+    #[serde(default)]
+    pub extensions: BTreeMap<String, String>,
 }
 
 /// Performance configuration
@@ -96,24 +191,26 @@ impl Default for DisplayConfig {
     fn default() -> Self {
         Self {
             unicode: true,
+            charset: None,
             show_size: true,
             show_lines: false,
             dir_sizes: false,
             total_size: true,
+            icons: false,
+            time_style: None,
+            units: SizeUnits::Iec,
+            precision: default_precision(),
         }
     }
 }
 
-impl Default for FilterConfig {
+impl Default for IconConfig {
     fn default() -> Self {
         Self {
-            show_hidden: false,
-            gitignore: false,
-            max_depth: None,
-            max_dirs: None,
-            max_files: None,
-            sort_by: None,
-            reverse_sort: false,
+            spacing: default_icon_spacing(),
+            dirs: BTreeMap::new(),
+            files: BTreeMap::new(),
+            extensions: BTreeMap::new(),
         }
     }
 }
@@ -179,6 +276,14 @@ fn default_true() -> bool {
     true
 }
 
+fn default_precision() -> usize {
+    1
+}
+
+fn default_icon_spacing() -> usize {
+    1
+}
+
 fn default_max_file_size() -> u64 {
     1_073_741_824 // 1GB
 }
@@ -204,4 +309,15 @@ mod tests {
         assert_eq!(config.display.show_size, parsed.display.show_size);
         assert_eq!(config.filters.gitignore, parsed.filters.gitignore);
     }
+    
+    #[test]
+    fn test_time_style_config() {
+        let config: Config = toml::from_str("[display]\ntime_style = \"+%d.%m.%Y\"").unwrap();
+        assert_eq!(config.display.time_style, Some(TimeStyle::Custom("%d.%m.%Y".to_string())));
+        
+        let toml_str = toml::to_string(&config).unwrap();
+        assert!(toml_str.contains("time_style = \"+%d.%m.%Y\""));
+        
+        assert!(toml::from_str::<Config>("[display]\ntime_style = \"fancy\"").is_err());
+    }
 }
\ No newline at end of file
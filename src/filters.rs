@@ -3,12 +3,18 @@
 //! This module provides all the logic for filtering files based on various criteria,
 //! sorting entries, and searching through the tree structure.
 
+use crate::classify::Classifier;
+use crate::units::parse_unit;
 use crate::{Args, Config, Error, Result};
 use clap::ValueEnum;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::fmt;
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 /// Sorting criteria for tree entries
@@ -17,18 +23,111 @@ use std::time::{Duration, SystemTime};
 pub enum SortBy {
     /// Sort by name (alphabetical)
     Name,
-    /// Sort by file size
+    /// Sort by file size (recursive size for directories)
     Size,
     /// Sort by modification time
     Time,
     /// Sort by file extension
     Ext,
-    /// Sort by line count (only for files)
+    /// Sort by line count (recursive line count for directories)
     Lines,
+    /// Sort by number of files (recursive file count for directories)
+    Files,
+    /// Sort by name, comparing embedded numbers numerically (file2 < file10)
+    Natural,
+    /// Sort by name as version strings, like `ls -v` (1.9 < 1.10, 1.0~rc1 < 1.0)
+    Version,
 }
 
+impl SortBy {
+    /// Whether this key needs recursive totals for directories
+    pub fn uses_dir_totals(self) -> bool {
+        matches!(self, SortBy::Size | SortBy::Lines | SortBy::Files)
+    }
+}
+
+/// A sort field with its direction, written as `field[:asc|:desc]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    /// Field to compare
+    pub field: SortBy,
+    /// Compare in descending order
+    pub descending: bool,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (field, direction) = match s.split_once(':') {
+            Some((field, direction)) => (field, Some(direction)),
+            None => (s, None),
+        };
+        
+        let field = SortBy::from_str(field.trim(), true)
+            .map_err(|_| format!("unknown sort field '{}'", field.trim()))?;
+        let descending = match direction.map(|d| d.trim().to_lowercase()).as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => return Err(format!("unknown sort direction '{}' (use asc or desc)", other)),
+        };
+        
+        Ok(Self { field, descending })
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = self.field.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+        write!(f, "{}{}", field, if self.descending { ":desc" } else { "" })
+    }
+}
+
+/// Parse a comma-separated list of sort keys (e.g. "size:desc,name")
+pub fn parse_sort_keys(spec: &str) -> Result<Vec<SortKey>> {
+    spec.split(',')
+        .filter(|key| !key.trim().is_empty())
+        .map(|key| key.parse().map_err(|e| Error::config(format!("Invalid sort key: {}", e))))
+        .collect()
+}
+
+/// How directories and files are grouped within a directory listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Grouping {
+    /// Directories and files are sorted together
+    #[default]
+    Mixed,
+    /// Directories are listed before files
+    DirsFirst,
+    /// Files are listed before directories
+    FilesFirst,
+}
+
+impl Grouping {
+    /// Order two entries by group only (never reversed by `--reverse`)
+    pub fn compare(self, a_is_dir: bool, b_is_dir: bool) -> Ordering {
+        match self {
+            Grouping::Mixed => Ordering::Equal,
+            Grouping::DirsFirst => b_is_dir.cmp(&a_is_dir),
+            Grouping::FilesFirst => a_is_dir.cmp(&b_is_dir),
+        }
+    }
+}
+
+/// Directories collapsed by `--collapse` when no patterns are given
+pub const DEFAULT_COLLAPSE: &[&str] = &[
+    "node_modules",
+    "target",
+    ".venv",
+    "venv",
+    ".git",
+    "__pycache__",
+    ".tox",
+    ".gradle",
+];
+
 /// Options for filtering directory entries
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct FilterOptions {
     /// Include pattern (regex)
     pub include: Option<Regex>,
@@ -50,41 +149,41 @@ pub struct FilterOptions {
     pub gitignore: bool,
     /// Show hidden files
     pub show_hidden: bool,
+    /// Show only zero-byte files and directories with no entries
+    pub empty_only: bool,
+    /// Hide directories left empty after filtering
+    pub prune: bool,
+    /// Show only files in this category
+    pub category: Option<String>,
+    /// File classifier extended with the config categories, shared with
+    /// `FormatOptions` so the globsets are compiled once
+    pub classifier: Arc<Classifier>,
     /// Search pattern (regex)
     pub search: Option<Regex>,
+    /// File content search pattern (regex)
+    pub contains: Option<Regex>,
+    /// Fuzzy path search query
+    pub fuzzy: Option<String>,
+    /// Number of fuzzy search results to keep
+    pub fuzzy_limit: usize,
     /// Maximum depth to traverse
     pub max_depth: Option<usize>,
     /// Maximum directories per level
     pub max_dirs: Option<usize>,
     /// Maximum files per level
     pub max_files: Option<usize>,
-    /// Sort criteria
-    pub sort_by: Option<SortBy>,
+    /// Sort keys, applied in order (empty = unsorted)
+    pub sort_keys: Vec<SortKey>,
     /// Reverse sort order
     pub reverse_sort: bool,
-}
-
-impl Default for FilterOptions {
-    fn default() -> Self {
-        Self {
-            include: None,
-            exclude: None,
-            only_dirs: false,
-            only_files: false,
-            min_size: None,
-            max_size: None,
-            newer_than: None,
-            older_than: None,
-            gitignore: false,
-            show_hidden: false,
-            search: None,
-            max_depth: None,
-            max_dirs: None,
-            max_files: None,
-            sort_by: None,
-            reverse_sort: false,
-        }
-    }
+    /// Compare names case-insensitively when sorting
+    pub ignore_case_sort: bool,
+    /// Grouping of directories and files
+    pub grouping: Grouping,
+    /// Directory name globs shown as a one-line summary instead of expanded
+    pub collapse: Option<GlobSet>,
+    /// Directories with more entries than this are not opened
+    pub file_limit: Option<usize>,
 }
 
 impl FilterOptions {
@@ -101,12 +200,43 @@ impl FilterOptions {
             older_than: None,
             gitignore: args.gitignore || config.filters.gitignore,
             show_hidden: args.all || config.filters.show_hidden,
+            empty_only: args.empty,
+            prune: args.prune,
+            category: args.category.clone(),
+            classifier: Arc::new(Classifier::new(&config.categories)?.with_root(Path::new(&args.path))),
             search: None,
+            contains: None,
+            fuzzy: args.fuzzy.clone(),
+            fuzzy_limit: args.fuzzy_limit,
             max_depth: args.depth.or(config.filters.max_depth),
             max_dirs: args.max_dirs.or(config.filters.max_dirs),
             max_files: args.max_files.or(config.filters.max_files),
-            sort_by: args.sort.or(config.filters.sort_by),
+            sort_keys: args.sort.clone(),
             reverse_sort: args.reverse || config.filters.reverse_sort,
+            ignore_case_sort: args.ignore_case_sort || config.filters.ignore_case_sort,
+            grouping: Grouping::Mixed,
+            collapse: None,
+            file_limit: args.filelimit.or(config.filters.filelimit),
+        };
+        
+        // Fall back to the config sort keys when none are given
+        if opts.sort_keys.is_empty() {
+            if let Some(spec) = &config.filters.sort_by {
+                opts.sort_keys = parse_sort_keys(spec)?;
+            }
+        }
+        
+        // Command line grouping overrides the config default
+        opts.grouping = if args.dirs_first {
+            Grouping::DirsFirst
+        } else if args.files_first {
+            Grouping::FilesFirst
+        } else if config.filters.dirs_first {
+            Grouping::DirsFirst
+        } else if config.filters.files_first {
+            Grouping::FilesFirst
+        } else {
+            Grouping::Mixed
         };
         
         // Compile regex patterns
@@ -122,6 +252,22 @@ impl FilterOptions {
             opts.search = Some(compile_regex(pattern, args.ignore_case)?);
         }
         
+        if let Some(pattern) = &args.contains {
+            opts.contains = Some(compile_regex(pattern, args.ignore_case)?);
+        }
+        
+        // A bare --collapse uses the config list, or the built-in one
+        let collapse = match &args.collapse {
+            Some(patterns) if !patterns.is_empty() => patterns.clone(),
+            Some(_) if config.filters.collapse.is_empty() => {
+                DEFAULT_COLLAPSE.iter().map(|pattern| pattern.to_string()).collect()
+            }
+            _ => config.filters.collapse.clone(),
+        };
+        if !collapse.is_empty() {
+            opts.collapse = Some(compile_globs(&collapse)?);
+        }
+        
         // Parse size filters
         if let Some(size_str) = &args.min_size {
             opts.min_size = Some(parse_size(size_str)?);
@@ -143,6 +289,14 @@ impl FilterOptions {
         Ok(opts)
     }
     
+    /// Check whether a directory is collapsed into a summary line
+    pub fn collapses(&self, path: &Path) -> bool {
+        match (&self.collapse, path.file_name()) {
+            (Some(globs), Some(name)) => globs.is_match(name),
+            _ => false,
+        }
+    }
+    
     /// Check if a path should be included based on filters
     pub fn should_include(&self, path: &Path, metadata: &std::fs::Metadata) -> bool {
         // Check if it's a directory or file
@@ -154,6 +308,16 @@ impl FilterOptions {
             return false;
         }
         
+        self.matches_filters(path, metadata)
+    }
+    
+    /// Check a path against every filter except `--only-dirs` and `--only-files`
+    ///
+    /// Directory totals use this, so a directory-only listing still sizes
+    /// directories by the files they hold.
+    pub fn matches_filters(&self, path: &Path, metadata: &std::fs::Metadata) -> bool {
+        let is_dir = metadata.is_dir();
+        
         // Check hidden files
         if !self.show_hidden {
             if let Some(name) = path.file_name() {
@@ -187,6 +351,9 @@ impl FilterOptions {
         // Check size filters (only for files)
         if !is_dir {
             let size = metadata.len();
+            if self.empty_only && size != 0 {
+                return false;
+            }
             if let Some(min) = self.min_size {
                 if size < min {
                     return false;
@@ -199,6 +366,15 @@ impl FilterOptions {
             }
         }
         
+        // Check category last since it may read the file header
+        if !is_dir {
+            if let Some(category) = &self.category {
+                if !self.classifier.classify(path).kind.eq_ignore_ascii_case(category) {
+                    return false;
+                }
+            }
+        }
+        
         // Check time filters
         if let Ok(modified) = metadata.modified() {
             let now = SystemTime::now();
@@ -219,6 +395,28 @@ impl FilterOptions {
         true
     }
     
+    /// Whether directories must be re-evaluated after their children are filtered
+    ///
+    /// Search and category filters imply pruning so only directories leading to
+    /// matches remain.
+    pub fn prunes_directories(&self) -> bool {
+        self.prune || self.empty_only || self.search.is_some() || self.category.is_some()
+    }
+    
+    /// Whether the listing can leave out entries the filters accept
+    ///
+    /// Depth and per-directory limits, `--collapse`, `--filelimit` and
+    /// content or fuzzy search all show less than the filtered tree.
+    pub fn limits_listing(&self) -> bool {
+        self.max_depth.is_some()
+            || self.max_dirs.is_some()
+            || self.max_files.is_some()
+            || self.collapse.is_some()
+            || self.file_limit.is_some()
+            || self.contains.is_some()
+            || self.fuzzy.is_some()
+    }
+    
     /// Check if a path matches the search pattern
     pub fn matches_search(&self, path: &Path) -> bool {
         if let Some(regex) = &self.search {
@@ -231,7 +429,7 @@ impl FilterOptions {
 }
 
 /// Compile a regex pattern with optional case insensitivity
-fn compile_regex(pattern: &str, ignore_case: bool) -> Result<Regex> {
+pub(crate) fn compile_regex(pattern: &str, ignore_case: bool) -> Result<Regex> {
     let mut builder = regex::RegexBuilder::new(pattern);
     if ignore_case {
         builder.case_insensitive(true);
@@ -239,32 +437,38 @@ fn compile_regex(pattern: &str, ignore_case: bool) -> Result<Regex> {
     builder.build().map_err(Into::into)
 }
 
+/// Compile glob patterns into a set
+fn compile_globs(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| Error::general(format!("Invalid collapse pattern '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder.build()
+        .map_err(|e| Error::general(format!("Invalid collapse patterns: {}", e)))
+}
+
 /// Parse a human-readable size string (e.g., "1MB", "500KB") into bytes
 fn parse_size(size_str: &str) -> Result<u64> {
-    let size_str = size_str.trim().to_uppercase();
+    let size_str = size_str.trim();
     
     // Extract number and unit
     let (num_str, unit) = if let Some(pos) = size_str.find(|c: char| c.is_alphabetic()) {
         size_str.split_at(pos)
     } else {
-        (size_str.as_str(), "")
+        (size_str, "")
     };
     
     // Parse the number
     let num: f64 = num_str.trim().parse()
         .map_err(|_| Error::size_parse(format!("Invalid number: {}", num_str)))?;
     
-    // Convert to bytes based on unit
-    let bytes = match unit.trim() {
-        "" | "B" => num,
-        "K" | "KB" => num * 1024.0,
-        "M" | "MB" => num * 1024.0 * 1024.0,
-        "G" | "GB" => num * 1024.0 * 1024.0 * 1024.0,
-        "T" | "TB" => num * 1024.0 * 1024.0 * 1024.0 * 1024.0,
-        _ => return Err(Error::size_parse(format!("Unknown size unit: {}", unit))),
-    };
+    // Convert to bytes based on unit (K/KiB = 1024, kB/KB = 1000)
+    let (base, power) = parse_unit(unit.trim())
+        .ok_or_else(|| Error::size_parse(format!("Unknown size unit: {}", unit)))?;
     
-    Ok(bytes as u64)
+    Ok((num * (base as f64).powi(power as i32)) as u64)
 }
 
 /// Parse a human-readable duration string (e.g., "1d", "2h", "30m") into a Duration
@@ -295,23 +499,52 @@ fn parse_duration(time_str: &str) -> Result<Duration> {
     Ok(Duration::from_secs(seconds))
 }
 
+/// Comparator for sorting entries by a list of keys
+///
+/// Keys are compared in order, each in its own direction; `reverse` flips
+/// the combined result.
+pub fn compare_by_keys(
+    a: &crate::walker::TreeEntry,
+    b: &crate::walker::TreeEntry,
+    keys: &[SortKey],
+    reverse: bool,
+    ignore_case: bool,
+) -> Ordering {
+    let ordering = keys.iter().fold(Ordering::Equal, |ordering, key| {
+        ordering.then_with(|| compare_entries(a, b, key.field, key.descending, ignore_case))
+    });
+    
+    if reverse {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
 /// Comparator for sorting entries
+///
+/// Directories compare by their recursive totals for size, lines and files.
 pub fn compare_entries(
     a: &crate::walker::TreeEntry,
     b: &crate::walker::TreeEntry,
     sort_by: SortBy,
     reverse: bool,
+    ignore_case: bool,
 ) -> Ordering {
     let ordering = match sort_by {
-        SortBy::Name => a.name.cmp(&b.name),
+        SortBy::Name => compare_names(&a.name, &b.name, ignore_case),
         SortBy::Size => a.size.cmp(&b.size),
         SortBy::Time => a.modified.cmp(&b.modified),
         SortBy::Ext => {
-            let ext_a = Path::new(&a.name).extension().unwrap_or_default();
-            let ext_b = Path::new(&b.name).extension().unwrap_or_default();
-            ext_a.cmp(&ext_b).then_with(|| a.name.cmp(&b.name))
+            let ext_a = Path::new(&a.name).extension().unwrap_or_default().to_string_lossy();
+            let ext_b = Path::new(&b.name).extension().unwrap_or_default().to_string_lossy();
+            compare_names(&ext_a, &ext_b, ignore_case)
+                .then_with(|| compare_names(&a.name, &b.name, ignore_case))
         }
         SortBy::Lines => a.line_count.cmp(&b.line_count),
+        SortBy::Files => file_total(a).cmp(&file_total(b)),
+        SortBy::Natural => with_case(&a.name, &b.name, ignore_case, natural_cmp),
+        SortBy::Version => with_case(&a.name, &b.name, ignore_case, version_cmp),
     };
     
     if reverse {
@@ -321,6 +554,170 @@ pub fn compare_entries(
     }
 }
 
+/// Number of files an entry accounts for (a file counts as itself)
+fn file_total(entry: &crate::walker::TreeEntry) -> u64 {
+    if entry.is_dir {
+        entry.file_count
+    } else {
+        1
+    }
+}
+
+/// Compare names byte-wise, optionally ignoring case
+fn compare_names(a: &str, b: &str, ignore_case: bool) -> Ordering {
+    with_case(a, b, ignore_case, |a, b| a.cmp(b))
+}
+
+/// Apply a name comparison, folding case first when requested
+///
+/// Names that only differ in case are ordered by the exact comparison so the
+/// result stays deterministic.
+fn with_case(a: &str, b: &str, ignore_case: bool, cmp: fn(&str, &str) -> Ordering) -> Ordering {
+    if ignore_case {
+        cmp(&a.to_lowercase(), &b.to_lowercase()).then_with(|| cmp(a, b))
+    } else {
+        cmp(a, b)
+    }
+}
+
+/// Natural order comparison: runs of digits compare by numeric value
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    
+    loop {
+        match (a.first(), b.first()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (num_a, rest_a) = split_digits(a);
+                let (num_b, rest_b) = split_digits(b);
+                let ordering = compare_digit_runs(num_a, num_b);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a = rest_a;
+                b = rest_b;
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(y);
+                }
+                a = &a[1..];
+                b = &b[1..];
+            }
+        }
+    }
+}
+
+/// Version comparison in the style of GNU `filevercmp` (`ls -v`)
+///
+/// File suffixes such as `.tar.gz` are compared last, `~` sorts before
+/// everything (so `1.0~rc1` < `1.0`), letters sort before other characters,
+/// and digit runs compare numerically.
+pub fn version_cmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    
+    // Hidden files sort before everything else
+    match (a.starts_with('.'), b.starts_with('.')) {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        _ => {}
+    }
+    
+    let (prefix_a, prefix_b) = (strip_version_suffix(a), strip_version_suffix(b));
+    let ordering = if prefix_a == prefix_b {
+        Ordering::Equal
+    } else {
+        verrevcmp(prefix_a.as_bytes(), prefix_b.as_bytes())
+    };
+    
+    ordering
+        .then_with(|| verrevcmp(a.as_bytes(), b.as_bytes()))
+        .then_with(|| a.cmp(b))
+}
+
+/// Strip a trailing file suffix matching `(\.[A-Za-z~][A-Za-z0-9~]*)*$`
+fn strip_version_suffix(name: &str) -> &str {
+    let bytes = name.as_bytes();
+    let mut end = bytes.len();
+    let mut i = bytes.len();
+    
+    while i > 0 {
+        i -= 1;
+        let c = bytes[i];
+        if c == b'.' {
+            // A suffix must start with a letter or `~` right after the dot
+            match bytes.get(i + 1) {
+                Some(next) if next.is_ascii_alphabetic() || *next == b'~' => end = i,
+                _ => break,
+            }
+        } else if !(c.is_ascii_alphanumeric() || c == b'~') {
+            break;
+        }
+    }
+    
+    &name[..end]
+}
+
+/// Debian-style version comparison of alternating non-digit and digit runs
+fn verrevcmp(mut a: &[u8], mut b: &[u8]) -> Ordering {
+    fn order(c: Option<&u8>) -> i32 {
+        match c {
+            None => 0,
+            Some(c) if c.is_ascii_digit() => 0,
+            Some(c) if c.is_ascii_alphabetic() => *c as i32,
+            Some(b'~') => -1,
+            Some(c) => *c as i32 + 256,
+        }
+    }
+    
+    while !a.is_empty() || !b.is_empty() {
+        // Compare the non-digit runs character by character
+        while a.first().is_some_and(|c| !c.is_ascii_digit())
+            || b.first().is_some_and(|c| !c.is_ascii_digit())
+        {
+            let (ord_a, ord_b) = (order(a.first()), order(b.first()));
+            if ord_a != ord_b {
+                return ord_a.cmp(&ord_b);
+            }
+            a = a.get(1..).unwrap_or_default();
+            b = b.get(1..).unwrap_or_default();
+        }
+        
+        // Compare the digit runs numerically
+        let (num_a, rest_a) = split_digits(a);
+        let (num_b, rest_b) = split_digits(b);
+        let ordering = compare_digit_runs(num_a, num_b);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        a = rest_a;
+        b = rest_b;
+    }
+    
+    Ordering::Equal
+}
+
+/// Split a leading run of ASCII digits off a byte string
+fn split_digits(s: &[u8]) -> (&[u8], &[u8]) {
+    let len = s.iter().take_while(|c| c.is_ascii_digit()).count();
+    s.split_at(len)
+}
+
+/// Compare two digit runs by numeric value without parsing (no overflow)
+fn compare_digit_runs(a: &[u8], b: &[u8]) -> Ordering {
+    let trim = |s: &[u8]| -> usize { s.iter().take_while(|&&c| c == b'0').count() };
+    let (a_trimmed, b_trimmed) = (&a[trim(a)..], &b[trim(b)..]);
+    
+    a_trimmed.len().cmp(&b_trimmed.len())
+        .then_with(|| a_trimmed.cmp(b_trimmed))
+        // Equal values: fewer leading zeros first (file1 < file01)
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,9 +725,14 @@ mod tests {
     #[test]
     fn test_parse_size() {
         assert_eq!(parse_size("100").unwrap(), 100);
-        assert_eq!(parse_size("1KB").unwrap(), 1024);
-        assert_eq!(parse_size("5MB").unwrap(), 5 * 1024 * 1024);
-        assert_eq!(parse_size("1.5GB").unwrap(), (1.5 * 1024.0 * 1024.0 * 1024.0) as u64);
+        assert_eq!(parse_size("1K").unwrap(), 1024);
+        assert_eq!(parse_size("1KiB").unwrap(), 1024);
+        assert_eq!(parse_size("1kB").unwrap(), 1000);
+        assert_eq!(parse_size("1KB").unwrap(), 1000);
+        assert_eq!(parse_size("5MiB").unwrap(), 5 * 1024 * 1024);
+        assert_eq!(parse_size("5MB").unwrap(), 5_000_000);
+        assert_eq!(parse_size("1.5GiB").unwrap(), (1.5 * 1024.0 * 1024.0 * 1024.0) as u64);
+        assert!(parse_size("1XB").is_err());
     }
     
     #[test]
@@ -340,4 +742,85 @@ mod tests {
         assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(2 * 60 * 60));
         assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(24 * 60 * 60));
     }
+    
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["file10", "file2", "file1", "file01", "a", "file"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["a", "file", "file1", "file01", "file2", "file10"]);
+        
+        assert_eq!(with_case("B", "a", true, natural_cmp), Ordering::Greater);
+        assert_eq!(with_case("B", "a", false, natural_cmp), Ordering::Less);
+    }
+    
+    #[test]
+    fn test_version_cmp() {
+        let mut names = vec![
+            "maram-1.10.tar.gz",
+            "maram-1.9.tar.gz",
+            "maram-1.0.tar.gz",
+            "maram-1.0~rc1.tar.gz",
+        ];
+        names.sort_by(|a, b| version_cmp(a, b));
+        assert_eq!(names, vec![
+            "maram-1.0~rc1.tar.gz",
+            "maram-1.0.tar.gz",
+            "maram-1.9.tar.gz",
+            "maram-1.10.tar.gz",
+        ]);
+        
+        assert_eq!(strip_version_suffix("maram-1.2.tar.gz"), "maram-1.2");
+        assert_eq!(version_cmp("v2", "v10"), Ordering::Less);
+    }
+    
+    #[test]
+    fn test_parse_sort_keys() {
+        let keys = parse_sort_keys("size:desc, name").unwrap();
+        assert_eq!(keys, vec![
+            SortKey { field: SortBy::Size, descending: true },
+            SortKey { field: SortBy::Name, descending: false },
+        ]);
+        assert_eq!(keys[0].to_string(), "size:desc");
+        
+        assert!(parse_sort_keys("bogus").is_err());
+        assert!(parse_sort_keys("size:sideways").is_err());
+    }
+    
+    #[test]
+    fn test_grouping() {
+        assert_eq!(Grouping::DirsFirst.compare(true, false), Ordering::Less);
+        assert_eq!(Grouping::FilesFirst.compare(true, false), Ordering::Greater);
+        assert_eq!(Grouping::Mixed.compare(true, false), Ordering::Equal);
+    }
+    
+    #[test]
+    fn test_collapse_patterns() {
+        let mut config = Config::default();
+        let mut args = Args::default();
+        let opts = FilterOptions::from_args_and_config(&args, &config).unwrap();
+        assert!(!opts.collapses(Path::new("web/node_modules")));
+        
+        // A bare --collapse uses the built-in list
+        args.collapse = Some(Vec::new());
+        let opts = FilterOptions::from_args_and_config(&args, &config).unwrap();
+        assert!(opts.collapses(Path::new("web/node_modules")));
+        assert!(opts.collapses(Path::new("crate/target")));
+        assert!(!opts.collapses(Path::new("web/src")));
+        
+        // ... or the config list when there is one
+        config.filters.collapse = vec!["vendor".to_string()];
+        let opts = FilterOptions::from_args_and_config(&args, &config).unwrap();
+        assert!(opts.collapses(Path::new("app/vendor")));
+        assert!(!opts.collapses(Path::new("web/node_modules")));
+        
+        // Given patterns are globs matched against the directory name
+        args.collapse = Some(vec!["build-*".to_string(), "dist".to_string()]);
+        let opts = FilterOptions::from_args_and_config(&args, &config).unwrap();
+        assert!(opts.collapses(Path::new("app/build-debug")));
+        assert!(opts.collapses(Path::new("app/dist")));
+        assert!(!opts.collapses(Path::new("dist/app")));
+        
+        args.collapse = Some(vec!["[".to_string()]);
+        assert!(FilterOptions::from_args_and_config(&args, &config).is_err());
+    }
 }
\ No newline at end of file
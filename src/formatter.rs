@@ -3,13 +3,26 @@
 //! This module handles all output formatting including tree visualization,
 //! JSON/CSV export, and beautiful size distribution charts.
 
+use crate::classify::Classifier;
+use crate::colors::{paint_name, LsColors};
+use crate::icons::Icons;
+use crate::stats::{Largest, SizedPath, TimeStyle};
+use crate::units::SizeFormat;
+use crate::filters::compile_regex;
+use crate::languages::{summarize_languages, CodeStats, LanguageSummary};
+use crate::search::name_match_ranges;
+use crate::users::user_name;
 use crate::{Args, Config, Result, TreeEntry, TreeStats};
 use clap::ValueEnum;
 use colored::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::sync::Arc;
 
 /// Output format options
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -33,6 +46,68 @@ pub enum DistributionType {
     Size,
     /// Distribution by file extension
     Ext,
+    /// Distribution by detected programming language
+    Language,
+    /// Distribution by top-level directory
+    Dir,
+    /// Distribution by time since last modification
+    Age,
+    /// Distribution by file owner
+    Owner,
+    /// Distribution by depth below the root
+    Depth,
+}
+
+/// What a distribution adds up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DistributionMetric {
+    /// Total size in bytes
+    #[default]
+    Bytes,
+    /// Number of files
+    Files,
+    /// Number of lines
+    Lines,
+}
+
+impl DistributionMetric {
+    /// This metric's value for a file
+    fn value(self, entry: &TreeEntry) -> u64 {
+        match self {
+            DistributionMetric::Bytes => entry.size,
+            DistributionMetric::Files => 1,
+            DistributionMetric::Lines => entry.line_count,
+        }
+    }
+    
+    /// Format a total of this metric
+    fn format(self, value: u64, opts: &FormatOptions) -> String {
+        match self {
+            DistributionMetric::Bytes => opts.size_format.format(value),
+            DistributionMetric::Files | DistributionMetric::Lines => value.to_string(),
+        }
+    }
+    
+    /// Column heading for this metric
+    fn heading(self) -> &'static str {
+        match self {
+            DistributionMetric::Bytes => "Size",
+            DistributionMetric::Files => "Files",
+            DistributionMetric::Lines => "Lines",
+        }
+    }
+}
+
+/// Where distribution results go: human text or structured data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DistributionOutput {
+    /// Table or chart, as chosen by `--format`
+    #[default]
+    Text,
+    /// JSON document
+    Json,
+    /// CSV rows
+    Csv,
 }
 
 /// Distribution output format
@@ -47,23 +122,64 @@ pub enum DistributionFormat {
 /// Options for formatting output
 #[derive(Debug, Clone)]
 pub struct FormatOptions {
-    /// Use Unicode characters for tree
+    /// Use Unicode characters for charts
     pub unicode: bool,
+    /// Tree drawing characters
+    pub tree_chars: TreeChars,
     /// Use colored output
     pub color: bool,
-    /// Show full paths
-    pub full_path: bool,
+    /// How entry paths and the root are shown
+    pub paths: PathDisplay,
+    /// Collapse chains of single-subdirectory directories into one line
+    pub compact_dirs: bool,
+    /// Line budget the tree is fitted to by expanding only some directories
+    pub fit: Option<usize>,
     /// Show file sizes
     pub show_size: bool,
     /// Show line counts
     pub show_lines: bool,
     /// Show directory sizes
     pub dir_sizes: bool,
+    /// Show per-language code statistics
+    pub code_stats: bool,
+    /// Show the first matching line of content search results
+    pub first_match: bool,
+    /// Search pattern whose matches are highlighted in names
+    pub highlight: Option<Regex>,
+    /// File classifier for category colours and distributions
+    pub classifier: Arc<Classifier>,
+    /// Name colours from `LS_COLORS` and the config
+    pub colors: LsColors,
+    /// Icons shown before names (None = no icons)
+    pub icons: Option<Icons>,
+    /// Long listing with aligned detail columns
+    pub long: bool,
+    /// Show permissions
+    pub show_permissions: bool,
+    /// Show owner names
+    pub show_owner: bool,
+    /// Show group names
+    pub show_group: bool,
+    /// Show inode numbers
+    pub show_inodes: bool,
+    /// Show device IDs
+    pub show_device: bool,
+    /// Show modification times
+    pub show_date: bool,
+    /// Timestamp format; CSV and JSON keep epoch times when unset
+    pub time_style: Option<TimeStyle>,
+    /// Units and precision for sizes
+    pub size_format: SizeFormat,
+    /// Write CSV and JSON sizes as formatted strings
+    pub human_sizes: bool,
+    /// Entries carry extended metadata (adds CSV columns)
+    pub extended_metadata: bool,
 }
 
 impl FormatOptions {
-    /// Create format options from args and config
-    pub fn from_args_and_config(args: &Args, config: &Config) -> Self {
+    /// Create format options from args and config, reusing the classifier
+    /// already built for `FilterOptions`
+    pub fn from_args_and_config(args: &Args, config: &Config, classifier: Arc<Classifier>) -> Self {
         let color = if args.no_color {
             false
         } else if args.color {
@@ -73,53 +189,253 @@ impl FormatOptions {
             atty::is(atty::Stream::Stdout) && std::env::var("NO_COLOR").is_err()
         };
         
+        let charset = args.tree_charset(config);
+        
         Self {
-            unicode: args.unicode || config.display.unicode,
+            unicode: charset != Charset::Ascii,
+            tree_chars: TreeChars::new(charset),
             color,
-            full_path: args.full_path,
+            paths: PathDisplay::new(
+                Path::new(&args.path),
+                if args.absolute {
+                    PathStyle::Absolute
+                } else if args.full_path {
+                    PathStyle::Relative
+                } else {
+                    PathStyle::Name
+                },
+                args.root_label.clone(),
+            ),
+            compact_dirs: args.compact_dirs,
+            // Leave room for the summary and the prompt below the tree
+            fit: args.fit.map(|lines| lines.unwrap_or_else(|| terminal_height().saturating_sub(3))),
             show_size: args.show_size,
             show_lines: args.show_lines,
             dir_sizes: args.dir_sizes,
+            code_stats: args.code_stats,
+            first_match: args.first_match,
+            // The pattern was already validated when building FilterOptions
+            highlight: args.search.as_ref()
+                .and_then(|pattern| compile_regex(pattern, args.ignore_case).ok()),
+            classifier,
+            colors: LsColors::from_env_and_config(&config.colors),
+            icons: (args.icons || config.display.icons).then(|| Icons::new(&config.icons)),
+            long: args.long,
+            show_permissions: args.permissions,
+            show_owner: args.owner,
+            show_group: args.group,
+            show_inodes: args.inodes,
+            show_device: args.device,
+            show_date: args.date,
+            time_style: args.time_style.clone().or_else(|| config.display.time_style.clone()),
+            size_format: SizeFormat {
+                units: args.units.unwrap_or(config.display.units),
+                precision: args.precision.unwrap_or(config.display.precision),
+            },
+            human_sizes: args.human_sizes,
+            extended_metadata: args.needs_extended_metadata(),
+        }
+    }
+    
+    /// Whether any tree-style stat field is shown
+    fn shows_stat_fields(&self) -> bool {
+        self.show_permissions
+            || self.show_owner
+            || self.show_group
+            || self.show_inodes
+            || self.show_device
+            || self.show_date
+    }
+    
+    /// Format a timestamp in the chosen style (long ISO by default)
+    pub fn format_time(&self, time: SystemTime) -> String {
+        match &self.time_style {
+            Some(style) => style.format(time),
+            None => TimeStyle::LongIso.format(time),
         }
     }
 }
 
-/// Tree drawing characters
-struct TreeChars {
-    down: &'static str,
-    down_right: &'static str,
-    last: &'static str,
+/// How entries are named in tree output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    /// File names only (default)
+    #[default]
+    Name,
+    /// Paths below the root label, like `tree -f`
+    Relative,
+    /// Canonical absolute paths
+    Absolute,
 }
 
-impl TreeChars {
-    fn new(unicode: bool) -> Self {
-        if unicode {
-            Self {
-                down: "│   ",
-                down_right: "├── ",
-                last: "└── ",
-            }
+/// Root label and path style shared by every output format
+///
+/// Listings that always show whole paths (plain, CSV, JSON) join paths to
+/// the root label, or to the canonical root with `--absolute`.
+#[derive(Debug, Clone, Default)]
+pub struct PathDisplay {
+    /// How tree entries are named
+    pub style: PathStyle,
+    /// Root as given on the command line
+    root: PathBuf,
+    /// Canonical root
+    canonical_root: PathBuf,
+    /// Label overriding the root's name
+    label: Option<String>,
+}
+
+impl PathDisplay {
+    /// Paths below `root`, shown in the given style
+    pub fn new(root: &Path, style: PathStyle, label: Option<String>) -> Self {
+        Self {
+            style,
+            root: root.to_path_buf(),
+            canonical_root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            label,
+        }
+    }
+    
+    /// Text shown for the root entry
+    pub fn root_label(&self) -> String {
+        match (&self.label, self.style) {
+            (Some(label), _) => label.clone(),
+            (None, PathStyle::Absolute) => self.canonical_root.display().to_string(),
+            (None, _) => self.root.display().to_string(),
+        }
+    }
+    
+    /// Whole path of an entry, for listings of paths
+    pub fn full(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.canonical_root)
+            .or_else(|_| path.strip_prefix(&self.root))
+            .unwrap_or(path);
+        if relative.as_os_str().is_empty() {
+            return self.root_label();
+        }
+        
+        match self.style {
+            PathStyle::Absolute => self.canonical_root.join(relative),
+            PathStyle::Name | PathStyle::Relative => Path::new(&self.root_label()).join(relative),
+        }
+        .display()
+        .to_string()
+    }
+    
+    /// Text shown for an entry in the tree
+    pub fn tree_name(&self, name: &str, path: &Path, depth: usize) -> String {
+        if depth == 0 {
+            self.root_label()
+        } else if self.style == PathStyle::Name {
+            name.to_string()
         } else {
-            Self {
-                down: "│   ",
-                down_right: "├── ",
-                last: "└── ",
-            }
+            self.full(path)
+        }
+    }
+}
+
+/// Tree drawing styles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Charset {
+    /// Plain ASCII (`|--`, `` `-- ``)
+    Ascii,
+    /// Unicode box drawing (`├──`, `└──`)
+    #[default]
+    Unicode,
+    /// Unicode with rounded corners (`╰──`)
+    Rounded,
+    /// Heavy box drawing (`┣━━`, `┗━━`)
+    Heavy,
+    /// Double box drawing (`╠══`, `╚══`)
+    Double,
+    /// Unicode with a two-column indent (`├ `, `└ `)
+    Compact,
+}
+
+/// Tree drawing characters for one indent level
+///
+/// Shared by the buffered printer and the streaming walker so both draw
+/// identical trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeChars {
+    /// Continuation of an ancestor that has more siblings below
+    pub down: &'static str,
+    /// Connector for an entry with more siblings below
+    pub down_right: &'static str,
+    /// Connector for the last entry of a directory
+    pub last: &'static str,
+    /// Indent under an ancestor that was the last entry
+    pub blank: &'static str,
+}
+
+impl TreeChars {
+    /// Characters for a charset
+    pub fn new(charset: Charset) -> Self {
+        let (down, down_right, last, blank) = match charset {
+            Charset::Ascii => ("|   ", "|-- ", "`-- ", "    "),
+            Charset::Unicode => ("│   ", "├── ", "└── ", "    "),
+            Charset::Rounded => ("│   ", "├── ", "╰── ", "    "),
+            Charset::Heavy => ("┃   ", "┣━━ ", "┗━━ ", "    "),
+            Charset::Double => ("║   ", "╠══ ", "╚══ ", "    "),
+            Charset::Compact => ("│ ", "├ ", "└ ", "  "),
+        };
+        
+        Self { down, down_right, last, blank }
+    }
+    
+    /// Graph for an entry: one indent per ancestor below the root, then the connector
+    ///
+    /// `ancestors` holds, for each ancestor level, whether that ancestor was
+    /// the last entry of its directory.
+    pub fn graph(&self, ancestors: &[bool], is_last: bool) -> String {
+        let mut graph = String::new();
+        for &ancestor_is_last in ancestors {
+            graph.push_str(if ancestor_is_last { self.blank } else { self.down });
         }
+        graph.push_str(if is_last { self.last } else { self.down_right });
+        graph
+    }
+    
+    /// Terminal columns taken by one indent level
+    pub fn width(&self) -> usize {
+        display_width(self.blank)
     }
 }
 
 /// Print tree visualization
 pub fn print_tree(entries: &[TreeEntry], opts: &FormatOptions) -> Result<()> {
-    let chars = TreeChars::new(opts.unicode);
+    // Counts come from the real tree, before directories are collapsed
+    let stats = TreeStats::from_entries(entries);
+    let reshaped;
+    let entries = if opts.compact_dirs || opts.fit.is_some() {
+        let mut tree = entries.to_vec();
+        if let Some(lines) = opts.fit {
+            fit_to_lines(&mut tree, lines);
+        }
+        if opts.compact_dirs {
+            tree = compact_dir_chains(tree);
+        }
+        reshaped = tree;
+        &reshaped
+    } else {
+        entries
+    };
+    
     let mut stdout = io::stdout();
+    let layout = if opts.long {
+        Some(ColumnLayout::measure(entries, opts, long_columns))
+    } else if opts.shows_stat_fields() {
+        Some(ColumnLayout::measure(entries, opts, stat_columns))
+    } else {
+        None
+    };
     
     for (i, entry) in entries.iter().enumerate() {
-        print_tree_entry(&mut stdout, entry, &chars, opts, Vec::new(), i == entries.len() - 1)?;
+        let is_last = i == entries.len() - 1;
+        print_tree_entry(&mut stdout, entry, opts, layout.as_ref(), Vec::new(), is_last)?;
     }
     
     // Print summary line like tree command
-    let stats = TreeStats::from_entries(entries);
     println!();
     println!("{} {}, {} {}",
         stats.dir_count,
@@ -128,75 +444,271 @@ pub fn print_tree(entries: &[TreeEntry], opts: &FormatOptions) -> Result<()> {
         if stats.file_count == 1 { "file" } else { "files" }
     );
     
+    if opts.highlight.is_some() {
+        println!("{}", match_summary(stats.file_count));
+    }
+    
     Ok(())
 }
 
+/// Collapse chains of directories that hold nothing but one subdirectory
+///
+/// `src/main/java` becomes a single entry named `src/main/java/` with the
+/// path and children of the last directory in the chain. It keeps the size
+/// and counts of the first, which already cover the whole chain. The root
+/// is never collapsed; depths are renumbered to match the drawn tree.
+fn compact_dir_chains(entries: Vec<TreeEntry>) -> Vec<TreeEntry> {
+    fn compact(mut entry: TreeEntry, depth: usize) -> TreeEntry {
+        if depth > 0 && entry.is_dir && entry.children.len() == 1 && entry.children[0].is_dir {
+            while entry.children.len() == 1 && entry.children[0].is_dir {
+                let child = entry.children.remove(0);
+                entry.name = format!("{}/{}", entry.name, child.name);
+                if child.collapsed {
+                    entry.size = child.size;
+                    entry.file_count = child.file_count;
+                    entry.collapsed = true;
+                }
+                entry.path = child.path;
+                entry.modified = child.modified;
+                entry.metadata = child.metadata;
+                entry.children = child.children;
+            }
+            entry.name.push('/');
+        }
+        
+        entry.depth = depth;
+        entry.children = entry.children
+            .into_iter()
+            .map(|child| compact(child, depth + 1))
+            .collect();
+        entry
+    }
+    
+    entries.into_iter().map(|entry| compact(entry, 0)).collect()
+}
+
+/// Expand only as many directories as fit in a budget of `lines` lines
+///
+/// Directories are opened breadth-first, one level at a time, and within a
+/// level those holding the most files (then the most bytes) go first. The
+/// rest become collapsed entries showing their file count and size. The
+/// root is always opened, even when its children alone exceed the budget.
+fn fit_to_lines(entries: &mut [TreeEntry], lines: usize) {
+    fn collapse(entry: &mut TreeEntry, expanded: &HashSet<PathBuf>) {
+        if !entry.children.is_empty() && !expanded.contains(&entry.path) {
+            let (files, size) = subtree_totals(entry);
+            entry.file_count = files;
+            entry.size = size;
+            entry.collapsed = true;
+            entry.children.clear();
+        }
+        for child in &mut entry.children {
+            collapse(child, expanded);
+        }
+    }
+    
+    let mut used = entries.len();
+    let mut expanded = HashSet::new();
+    let mut level: Vec<&TreeEntry> = Vec::new();
+    
+    for root in entries.iter() {
+        used += root.children.len();
+        expanded.insert(root.path.clone());
+        level.extend(root.children.iter().filter(|child| !child.children.is_empty()));
+    }
+    
+    while !level.is_empty() {
+        let mut candidates: Vec<(&TreeEntry, (u64, u64))> = level
+            .drain(..)
+            .map(|entry| (entry, subtree_totals(entry)))
+            .collect();
+        candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+        
+        for (entry, _) in candidates {
+            if used + entry.children.len() <= lines {
+                used += entry.children.len();
+                expanded.insert(entry.path.clone());
+                level.extend(entry.children.iter().filter(|child| !child.children.is_empty()));
+            }
+        }
+    }
+    
+    for entry in entries {
+        collapse(entry, &expanded);
+    }
+}
+
+/// Number of files below an entry and their total size
+///
+/// Directories summarised by `--collapse` or left unopened by `--filelimit`
+/// have no children, so their own counts stand in for them.
+fn subtree_totals(entry: &TreeEntry) -> (u64, u64) {
+    if entry.collapsed {
+        return (entry.file_count, entry.size);
+    }
+    if let Some(count) = entry.unopened_entries {
+        return (count as u64, entry.size);
+    }
+    
+    entry.children.iter().fold((0, 0), |(files, size), child| {
+        if child.is_dir {
+            let (child_files, child_size) = subtree_totals(child);
+            (files + child_files, size + child_size)
+        } else {
+            (files + 1, size + child.size)
+        }
+    })
+}
+
+/// Summary line for `--search`, where every listed file is a match
+pub(crate) fn match_summary(count: usize) -> String {
+    format!("{} {}", count, if count == 1 { "match" } else { "matches" })
+}
+
 /// Print a single tree entry recursively
+///
+/// `prefix` holds, for each ancestor below the root, whether it was the last
+/// entry of its directory. The root itself is printed without a connector.
 fn print_tree_entry(
     out: &mut dyn Write,
     entry: &TreeEntry,
-    chars: &TreeChars,
     opts: &FormatOptions,
+    layout: Option<&ColumnLayout>,
     prefix: Vec<bool>,
     is_last: bool,
 ) -> Result<()> {
-    // Print prefix
-    for &cont in &prefix {
-        write!(out, "{}", if cont { chars.down } else { "    " })?;
+    let is_root = entry.depth == 0;
+    
+    // Print long listing columns ahead of the tree graph
+    if let Some(layout) = layout.filter(|_| opts.long) {
+        write!(out, "{} ", layout.render(entry, opts))?;
     }
     
-    // Print connector
-    write!(out, "{}", if is_last { chars.last } else { chars.down_right })?;
+    // Print prefix and connector
+    if !is_root {
+        write!(out, "{}", opts.tree_chars.graph(&prefix, is_last))?;
+    }
+    
+    // Print tree-style `[perms user group]` fields after the connector
+    if let Some(layout) = layout.filter(|_| !opts.long) {
+        write!(out, "[{}] ", layout.render(entry, opts))?;
+    }
+    
+    // Print icon
+    if let Some(icons) = &opts.icons {
+        write!(out, "{}", icons.prefix(&entry.name, entry.is_dir, entry.is_symlink, entry.is_executable))?;
+    }
+    
+    // Shorten the name to the space left on the line in long mode
+    let display_name = opts.paths.tree_name(&entry.name, &entry.path, entry.depth);
+    let budget = layout.and_then(|layout| layout.name_budget(entry.depth, opts));
+    let (shown, truncated) = truncate_to_width(&display_name, budget);
     
     // Format name with color
-    let name = if opts.color {
-        if entry.is_dir {
-            entry.name.blue().bold().to_string()
-        } else if entry.is_symlink {
-            entry.name.cyan().to_string()
-        } else if entry.is_executable {
-            entry.name.green().to_string()
-        } else {
-            entry.name.clone()
+    let mut name = if opts.color {
+        // Name matches sit at the end of a full path; the root is never one,
+        // as in the streaming walker
+        let offset = display_name.strip_suffix(entry.name.as_str()).map(str::len);
+        let highlights: Vec<_> = match (offset, &entry.fuzzy, &opts.highlight) {
+            _ if is_root => Vec::new(),
+            (None, _, _) => Vec::new(),
+            (Some(_), Some(fuzzy), _) => fuzzy.name_ranges(&entry.name),
+            (Some(_), None, Some(regex)) => name_match_ranges(regex, &entry.path, &entry.name),
+            (Some(_), None, None) => Vec::new(),
         }
+        .into_iter()
+        .map(|range| {
+            let offset = offset.unwrap_or(0);
+            range.start + offset..range.end + offset
+        })
+        .filter(|range| range.start < shown.len())
+        .map(|range| range.start..range.end.min(shown.len()))
+        .collect();
+        let color = if opts.classifier.has_colors() && !entry.is_dir {
+            let kind = entry.kind.clone()
+                .unwrap_or_else(|| opts.classifier.classify_name(&entry.path));
+            opts.classifier.color(&kind)
+        } else {
+            None
+        };
+        paint_name(shown, &entry.path, entry.indicator, &opts.colors, color, &highlights)
     } else {
-        entry.name.clone()
+        shown.to_string()
     };
+    if truncated {
+        name.push('…');
+    }
     
-    // Add details
+    // Add details; long mode shows size and lines in columns instead
     let mut details = Vec::new();
     
-    if opts.show_size && (!entry.is_dir || opts.dir_sizes) {
-        details.push(format_size(entry.size));
+    if entry.collapsed {
+        details.push(format_collapsed(entry.file_count, entry.size, &opts.size_format));
+    } else if opts.show_size && !opts.long && (!entry.is_dir || opts.dir_sizes) {
+        details.push(opts.size_format.format(entry.size));
     }
     
-    if opts.show_lines && entry.line_count > 0 {
+    if opts.show_lines && !opts.long && entry.line_count > 0 {
         details.push(format!("{} lines", entry.line_count));
     }
     
+    if opts.code_stats {
+        if let Some(stats) = &entry.code_stats {
+            details.push(format_code_stats(stats));
+        }
+    }
+    
+    if let Some(matches) = &entry.matches {
+        details.push(format!(
+            "{} {}",
+            matches.count,
+            if matches.count == 1 { "match" } else { "matches" }
+        ));
+    }
+    
     // Print entry
     if details.is_empty() {
-        writeln!(out, "{}", name)?;
+        write!(out, "{}", name)?;
     } else {
         let detail_str = if opts.color {
             format!(" ({})", details.join(", ")).dimmed().to_string()
         } else {
             format!(" ({})", details.join(", "))
         };
-        writeln!(out, "{}{}", name, detail_str)?;
+        write!(out, "{}{}", name, detail_str)?;
+    }
+    
+    if let Some(count) = entry.unopened_entries {
+        write!(out, " {}", format_unopened(count))?;
+    }
+    
+    // Show the first matching line after the details
+    if opts.first_match {
+        if let Some(first) = entry.matches.as_ref().and_then(|m| m.first()) {
+            let line_no = format!("{}:", first.line);
+            let text = first.text.trim();
+            if opts.color {
+                write!(out, "  {} {}", line_no.green(), text)?;
+            } else {
+                write!(out, "  {} {}", line_no, text)?;
+            }
+        }
     }
+    writeln!(out)?;
     
     // Print children
     if !entry.children.is_empty() {
         let mut new_prefix = prefix;
-        new_prefix.push(!is_last);
+        if !is_root {
+            new_prefix.push(is_last);
+        }
         
         for (i, child) in entry.children.iter().enumerate() {
             print_tree_entry(
                 out,
                 child,
-                chars,
                 opts,
+                layout,
                 new_prefix.clone(),
                 i == entry.children.len() - 1,
             )?;
@@ -206,59 +718,414 @@ fn print_tree_entry(
     Ok(())
 }
 
-/// Print JSON output
-pub fn print_json(entries: &[TreeEntry]) -> Result<()> {
-    let json = serde_json::to_string_pretty(entries)?;
-    println!("{}", json);
-    Ok(())
+/// Detail columns of an entry as (text, right-aligned)
+type ColumnFn = fn(&TreeEntry, &FormatOptions) -> Vec<(String, bool)>;
+
+/// Aligned detail columns, measured over the whole tree before printing
+struct ColumnLayout {
+    /// Produces the columns of an entry
+    columns: ColumnFn,
+    /// Width of each detail column
+    widths: Vec<usize>,
+    /// Terminal width when writing a long listing to a terminal
+    term_width: Option<usize>,
 }
 
-/// Print CSV output
-pub fn print_csv(entries: &[TreeEntry]) -> Result<()> {
-    println!("path,type,size,lines,modified");
+impl ColumnLayout {
+    /// Narrowest space kept for a name before it is truncated
+    const MIN_NAME_WIDTH: usize = 8;
     
-    fn print_csv_entry(entry: &TreeEntry, parent_path: &str) -> Result<()> {
-        let path = if parent_path.is_empty() {
-            entry.name.clone()
+    /// Width pre-pass over every entry
+    fn measure(entries: &[TreeEntry], opts: &FormatOptions, columns: ColumnFn) -> Self {
+        fn visit(entry: &TreeEntry, opts: &FormatOptions, columns: ColumnFn, widths: &mut Vec<usize>) {
+            for (i, (text, _)) in columns(entry, opts).iter().enumerate() {
+                let width = display_width(text);
+                match widths.get_mut(i) {
+                    Some(w) => *w = (*w).max(width),
+                    None => widths.push(width),
+                }
+            }
+            for child in &entry.children {
+                visit(child, opts, columns, widths);
+            }
+        }
+        
+        let mut widths = Vec::new();
+        for entry in entries {
+            visit(entry, opts, columns, &mut widths);
+        }
+        
+        Self {
+            columns,
+            widths,
+            term_width: (opts.long && atty::is(atty::Stream::Stdout)).then(terminal_width),
+        }
+    }
+    
+    /// Aligned detail columns for an entry
+    fn render(&self, entry: &TreeEntry, opts: &FormatOptions) -> String {
+        let columns: Vec<String> = (self.columns)(entry, opts)
+            .into_iter()
+            .zip(&self.widths)
+            .map(|((text, right), &width)| {
+                let pad = " ".repeat(width.saturating_sub(display_width(&text)));
+                if right { pad + &text } else { text + &pad }
+            })
+            .collect();
+        
+        let line = columns.join(if opts.long { "  " } else { " " });
+        if opts.color {
+            line.dimmed().to_string()
+        } else {
+            line
+        }
+    }
+    
+    /// Columns left for a name at the given depth, when the terminal width is known
+    fn name_budget(&self, depth: usize, opts: &FormatOptions) -> Option<usize> {
+        let term_width = self.term_width?;
+        let columns: usize = self.widths.iter().sum::<usize>() + 2 * self.widths.len().saturating_sub(1) + 1;
+        let graph = depth * opts.tree_chars.width();
+        let icon = opts.icons.as_ref().map_or(0, Icons::width);
+        
+        Some(term_width.saturating_sub(columns + graph + icon).max(Self::MIN_NAME_WIDTH))
+    }
+}
+
+/// Columns of the long view: stat fields, size, lines and mtime
+fn long_columns(entry: &TreeEntry, opts: &FormatOptions) -> Vec<(String, bool)> {
+    let mut columns = stat_columns(entry, opts);
+    
+    let size = if !entry.is_dir || opts.dir_sizes || entry.collapsed {
+        opts.size_format.format(entry.size)
+    } else {
+        "-".to_string()
+    };
+    columns.push((size, true));
+    
+    if opts.show_lines {
+        let lines = if entry.line_count > 0 {
+            entry.line_count.to_string()
         } else {
-            format!("{}/{}", parent_path, entry.name)
+            "-".to_string()
         };
+        columns.push((lines, true));
+    }
+    
+    columns.push((opts.format_time(entry.modified), false));
+    columns
+}
+
+/// Stat fields in `tree` order: inode, device, permissions, owner, group, date
+///
+/// The long view always shows permissions and owner, and the date in its
+/// own column after the size.
+fn stat_columns(entry: &TreeEntry, opts: &FormatOptions) -> Vec<(String, bool)> {
+    let meta = entry.metadata.as_ref();
+    let field = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let mut columns = Vec::new();
+    
+    if opts.show_inodes {
+        columns.push((field(meta.map(|m| m.inode.to_string())), true));
+    }
+    if opts.show_device {
+        columns.push((field(meta.map(|m| m.device.to_string())), true));
+    }
+    if opts.show_permissions || opts.long {
+        columns.push((field(meta.map(|m| format_mode(m.mode))), false));
+    }
+    if opts.show_owner || opts.long {
+        columns.push((field(meta.map(|m| m.user.clone())), false));
+    }
+    if opts.show_group {
+        columns.push((field(meta.map(|m| m.group.clone())), false));
+    }
+    if opts.show_date && !opts.long {
+        columns.push((opts.format_time(entry.modified), false));
+    }
+    
+    columns
+}
+
+/// Render mode bits like `ls -l` (e.g. `drwxr-xr-x`)
+pub fn format_mode(mode: u32) -> String {
+    let file_type = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o010000 => 'p',
+        0o140000 => 's',
+        0o060000 => 'b',
+        0o020000 => 'c',
+        _ => '-',
+    };
+    
+    // Execute slot letter, given the special bit that shares it
+    let exec = |bit: u32, special: bool, set: char| -> char {
+        match (mode & bit != 0, special) {
+            (true, true) => set,
+            (false, true) => set.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        }
+    };
+    let flag = |bit: u32, c: char| if mode & bit != 0 { c } else { '-' };
+    
+    [
+        file_type,
+        flag(0o400, 'r'),
+        flag(0o200, 'w'),
+        exec(0o100, mode & 0o4000 != 0, 's'),
+        flag(0o040, 'r'),
+        flag(0o020, 'w'),
+        exec(0o010, mode & 0o2000 != 0, 's'),
+        flag(0o004, 'r'),
+        flag(0o002, 'w'),
+        exec(0o001, mode & 0o1000 != 0, 't'),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Terminal columns needed to display text
+///
+/// East Asian wide characters take two columns and combining marks none;
+/// everything else, including Nerd Font glyphs, takes one.
+pub(crate) fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Cut text to fit a width, reporting whether it was shortened
+///
+/// One column is left for the `…` marker when text is cut.
+fn truncate_to_width(text: &str, width: Option<usize>) -> (&str, bool) {
+    let Some(width) = width else {
+        return (text, false);
+    };
+    if display_width(text) <= width {
+        return (text, false);
+    }
+    
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += display_width(c.encode_utf8(&mut [0; 4]));
+        if used > width.saturating_sub(1) {
+            return (&text[..i], true);
+        }
+    }
+    (text, false)
+}
+
+/// Tree entries as JSON
+///
+/// Paths are shown like every other listing. With a time style, timestamps
+/// are formatted strings instead of epoch objects; with human sizes, sizes
+/// are formatted strings too.
+pub fn tree_json(entries: &[TreeEntry], opts: &FormatOptions) -> Result<serde_json::Value> {
+    let mut json = serde_json::to_value(entries)?;
+    humanize_json(&mut json, entries, opts);
+    Ok(json)
+}
+
+/// Summary counts of a tree as JSON, like the line below the tree
+pub fn summary_json(stats: &TreeStats) -> serde_json::Value {
+    serde_json::json!({
+        "directories": stats.dir_count,
+        "files": stats.file_count,
+    })
+}
+
+/// Size and line totals of a tree as JSON
+pub fn totals_json(stats: &TreeStats, opts: &FormatOptions) -> serde_json::Value {
+    serde_json::json!({
+        "size": json_size(stats.total_size, opts),
+        "file_size": json_size(stats.file_size, opts),
+        "dir_size": json_size(stats.dir_size, opts),
+        "lines": stats.total_lines,
+    })
+}
+
+/// Print the single JSON document written by `--output json`
+pub fn print_json_document(document: serde_json::Map<String, serde_json::Value>) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+/// Replace serialized paths, timestamps and sizes with displayed ones,
+/// walking entries alongside
+fn humanize_json(values: &mut serde_json::Value, entries: &[TreeEntry], opts: &FormatOptions) {
+    let Some(values) = values.as_array_mut() else {
+        return;
+    };
+    
+    for (value, entry) in values.iter_mut().zip(entries) {
+        value["path"] = opts.paths.full(&entry.path).into();
         
-        let entry_type = if entry.is_dir { "directory" } else { "file" };
-        let modified = entry.modified.duration_since(std::time::UNIX_EPOCH)
+        if opts.human_sizes {
+            value["size"] = opts.size_format.format(entry.size).into();
+        }
+        
+        if let Some(style) = &opts.time_style {
+            value["modified"] = style.format(entry.modified).into();
+            
+            if let (Some(meta), Some(value)) = (&entry.metadata, value.get_mut("metadata")) {
+                value["accessed"] = style.format(meta.accessed).into();
+                value["changed"] = style.format(meta.changed).into();
+                if let Some(created) = meta.created {
+                    value["created"] = style.format(created).into();
+                }
+            }
+        }
+        
+        if let Some(children) = value.get_mut("children") {
+            humanize_json(children, &entry.children, opts);
+        }
+    }
+}
+
+/// Print CSV output
+pub fn print_csv(entries: &[TreeEntry], opts: &FormatOptions) -> Result<()> {
+    if opts.extended_metadata {
+        println!("path,type,size,lines,modified,mode,user,group,uid,gid,inode,device,nlink,accessed,changed,created");
+    } else {
+        println!("path,type,size,lines,modified");
+    }
+    
+    // Epoch seconds unless a time style was chosen
+    let time = |time: SystemTime| match &opts.time_style {
+        Some(style) => csv_field(&style.format(time)),
+        None => time.duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
-            .as_secs();
+            .as_secs()
+            .to_string(),
+    };
+    
+    fn print_csv_entry(
+        entry: &TreeEntry,
+        opts: &FormatOptions,
+        time: &dyn Fn(SystemTime) -> String,
+    ) -> Result<()> {
+        let path = csv_field(&opts.paths.full(&entry.path));
+        let entry_type = if entry.is_dir { "directory" } else { "file" };
+        let modified = time(entry.modified);
+        let size = if opts.human_sizes {
+            csv_field(&opts.size_format.format(entry.size))
+        } else {
+            entry.size.to_string()
+        };
         
-        println!("{},{},{},{},{}", path, entry_type, entry.size, entry.line_count, modified);
+        print!("{},{},{},{},{}", path, entry_type, size, entry.line_count, modified);
+        if opts.extended_metadata {
+            match &entry.metadata {
+                Some(m) => print!(
+                    ",{},{},{},{},{},{},{},{},{},{},{}",
+                    format_mode(m.mode),
+                    m.user,
+                    m.group,
+                    m.uid,
+                    m.gid,
+                    m.inode,
+                    m.device,
+                    m.nlink,
+                    time(m.accessed),
+                    time(m.changed),
+                    m.created.map(time).unwrap_or_default(),
+                ),
+                None => print!(",,,,,,,,,,,"),
+            }
+        }
+        println!();
         
         for child in &entry.children {
-            print_csv_entry(child, &path)?;
+            print_csv_entry(child, opts, time)?;
         }
         
         Ok(())
     }
     
     for entry in entries {
-        print_csv_entry(entry, "")?;
+        print_csv_entry(entry, opts, &time)?;
     }
     
     Ok(())
 }
 
+/// Quote a CSV field if it contains a separator, quote or newline
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
 /// Print plain text output
-pub fn print_plain(entries: &[TreeEntry]) -> Result<()> {
-    fn print_plain_entry(entry: &TreeEntry, depth: usize) -> Result<()> {
-        println!("{}{}", "  ".repeat(depth), entry.name);
+///
+/// Each line is a whole path below the root label, or an absolute path with
+/// `--absolute`, exactly as the streaming walker prints them.
+pub fn print_plain(entries: &[TreeEntry], opts: &FormatOptions) -> Result<()> {
+    fn print_plain_entry(entry: &TreeEntry, opts: &FormatOptions) -> Result<()> {
+        println!("{}", opts.paths.full(&entry.path));
         
         for child in &entry.children {
-            print_plain_entry(child, depth + 1)?;
+            print_plain_entry(child, opts)?;
         }
         
         Ok(())
     }
     
     for entry in entries {
-        print_plain_entry(entry, 0)?;
+        print_plain_entry(entry, opts)?;
+    }
+    
+    Ok(())
+}
+
+/// Print fuzzy search results as paths, best match first
+pub fn print_ranked_plain(entries: &[TreeEntry], opts: &FormatOptions) -> Result<()> {
+    fn collect<'a>(entry: &'a TreeEntry, ranked: &mut Vec<&'a TreeEntry>) {
+        if entry.fuzzy.is_some() {
+            ranked.push(entry);
+        }
+        for child in &entry.children {
+            collect(child, ranked);
+        }
+    }
+    
+    let mut ranked = Vec::new();
+    for entry in entries {
+        collect(entry, &mut ranked);
+    }
+    
+    let score = |entry: &TreeEntry| entry.fuzzy.as_ref().map_or(0, |f| f.score);
+    ranked.sort_by(|a, b| {
+        score(b).cmp(&score(a))
+            .then_with(|| a.path.as_os_str().len().cmp(&b.path.as_os_str().len()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for entry in ranked {
+        writeln!(out, "{}", opts.paths.full(&entry.path))?;
     }
     
     Ok(())
@@ -268,11 +1135,11 @@ pub fn print_plain(entries: &[TreeEntry]) -> Result<()> {
 pub fn print_total_size(stats: &TreeStats, opts: &FormatOptions) -> Result<()> {
     let total_str = format!(
         "\nTotal: {} ({} files: {}, {} directories: {})",
-        format_size(stats.total_size),
+        opts.size_format.format(stats.total_size),
         stats.file_count,
-        format_size(stats.file_size),
+        opts.size_format.format(stats.file_size),
         stats.dir_count,
-        format_size(stats.dir_size),
+        opts.size_format.format(stats.dir_size),
     );
     
     if opts.color {
@@ -284,73 +1151,275 @@ pub fn print_total_size(stats: &TreeStats, opts: &FormatOptions) -> Result<()> {
     Ok(())
 }
 
+/// Print the largest files and directories with their share of the total
+pub fn print_largest(largest: &Largest, opts: &FormatOptions) -> Result<()> {
+    for (title, items) in [("Largest files", &largest.files), ("Largest directories", &largest.dirs)] {
+        if items.is_empty() {
+            continue;
+        }
+        
+        println!("\n{}", if opts.color { title.bold().to_string() } else { title.to_string() });
+        
+        let sizes: Vec<String> = items.iter().map(|item| opts.size_format.format(item.size)).collect();
+        let width = sizes.iter().map(String::len).max().unwrap_or(0);
+        
+        for (item, size) in items.iter().zip(&sizes) {
+            let share = format!("{:>5.1}%", percent(item.size, largest.total_size));
+            let path = opts.paths.full(&item.path);
+            if opts.color {
+                println!("{:>width$}  {}  {}", size.bright_white(), share.dimmed(), path);
+            } else {
+                println!("{:>width$}  {}  {}", size, share, path);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Largest-item report as JSON, with displayed paths
+pub fn largest_json(largest: &Largest, opts: &FormatOptions) -> serde_json::Value {
+    let items = |items: &[SizedPath]| -> Vec<serde_json::Value> {
+        items.iter()
+            .map(|item| serde_json::json!({
+                "path": opts.paths.full(&item.path),
+                "size": json_size(item.size, opts),
+                "share": (percent(item.size, largest.total_size) * 100.0).round() / 100.0,
+            }))
+            .collect()
+    };
+    
+    serde_json::json!({
+        "total_size": json_size(largest.total_size, opts),
+        "files": items(&largest.files),
+        "dirs": items(&largest.dirs),
+    })
+}
+
+/// A size for JSON: bytes, or a formatted string with `--human-sizes`
+fn json_size(size: u64, opts: &FormatOptions) -> serde_json::Value {
+    if opts.human_sizes {
+        opts.size_format.format(size).into()
+    } else {
+        size.into()
+    }
+}
+
+/// Share of a total as a percentage (0 for an empty total)
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
 /// Print size distribution
 pub fn print_distribution(
     entries: &[TreeEntry],
     dist_type: &DistributionType,
     top: usize,
     format: &DistributionFormat,
+    metric: Option<DistributionMetric>,
+    opts: &FormatOptions,
+) -> Result<()> {
+    // Language statistics have their own columns unless a metric is chosen
+    if *dist_type == DistributionType::Language && metric.is_none() && *format == DistributionFormat::Table {
+        return print_language_table(entries, top, opts);
+    }
+    
+    let metric = metric.unwrap_or_default();
+    let (sorted, total) = top_distribution(entries, dist_type, top, metric, opts);
+    
+    match format {
+        DistributionFormat::Table => print_distribution_table(&sorted, total, metric, opts),
+        DistributionFormat::Chart => print_distribution_chart(&sorted, total, metric, opts),
+    }
+}
+
+/// Distribution as JSON: its grouping, metric, total and top items
+///
+/// `--dist=language` without `--dist-metric` gives the language table's
+/// files, code, comments and blank counts instead.
+pub fn distribution_json(
+    entries: &[TreeEntry],
+    dist_type: &DistributionType,
+    top: usize,
+    metric: Option<DistributionMetric>,
+    opts: &FormatOptions,
+) -> serde_json::Value {
+    // Language statistics keep the columns of the language table
+    if *dist_type == DistributionType::Language && metric.is_none() {
+        return language_json(entries, top);
+    }
+    
+    let metric = metric.unwrap_or_default();
+    let (sorted, total) = top_distribution(entries, dist_type, top, metric, opts);
+    let value = |value: u64| match metric {
+        DistributionMetric::Bytes => json_size(value, opts),
+        DistributionMetric::Files | DistributionMetric::Lines => value.into(),
+    };
+    
+    let items: Vec<serde_json::Value> = sorted.iter()
+        .map(|(category, amount)| serde_json::json!({
+            "category": category,
+            "value": value(*amount),
+            "share": (percent(*amount, total) * 100.0).round() / 100.0,
+        }))
+        .collect();
+    
+    serde_json::json!({
+        "by": value_name(dist_type),
+        "metric": value_name(&metric),
+        "total": value(total),
+        "items": items,
+    })
+}
+
+/// Print a distribution as a JSON document
+pub fn print_distribution_json(
+    entries: &[TreeEntry],
+    dist_type: &DistributionType,
+    top: usize,
+    metric: Option<DistributionMetric>,
     opts: &FormatOptions,
 ) -> Result<()> {
-    let distribution = calculate_distribution(entries, dist_type);
+    let json = distribution_json(entries, dist_type, top, metric, opts);
+    println!("{}", serde_json::to_string_pretty(&json)?);
+    Ok(())
+}
+
+/// Print a distribution as CSV rows of category, value and share
+///
+/// `--dist=language` without `--dist-metric` prints the language table's
+/// columns instead.
+pub fn print_distribution_csv(
+    entries: &[TreeEntry],
+    dist_type: &DistributionType,
+    top: usize,
+    metric: Option<DistributionMetric>,
+    opts: &FormatOptions,
+) -> Result<()> {
+    if *dist_type == DistributionType::Language && metric.is_none() {
+        return print_language_csv(entries, top);
+    }
+    
+    let metric = metric.unwrap_or_default();
+    let (sorted, total) = top_distribution(entries, dist_type, top, metric, opts);
+    
+    println!("category,value,share");
+    for (category, amount) in &sorted {
+        let value = if metric == DistributionMetric::Bytes && opts.human_sizes {
+            csv_field(&opts.size_format.format(*amount))
+        } else {
+            amount.to_string()
+        };
+        println!("{},{},{:.2}", csv_field(category), value, percent(*amount, total));
+    }
     
-    // Sort by size descending and take top N
+    Ok(())
+}
+
+/// Per-language code statistics as JSON, with their totals
+fn language_json(entries: &[TreeEntry], top: usize) -> serde_json::Value {
+    let mut summaries = summarize_languages(entries);
+    summaries.truncate(top);
+    
+    let mut total = LanguageSummary::default();
+    let items: Vec<serde_json::Value> = summaries.iter()
+        .map(|summary| {
+            total.files += summary.files;
+            total.code += summary.code;
+            total.comments += summary.comments;
+            total.blank += summary.blank;
+            serde_json::json!({
+                "language": summary.language,
+                "files": summary.files,
+                "code": summary.code,
+                "comments": summary.comments,
+                "blank": summary.blank,
+            })
+        })
+        .collect();
+    
+    serde_json::json!({
+        "by": value_name(&DistributionType::Language),
+        "total": {
+            "files": total.files,
+            "code": total.code,
+            "comments": total.comments,
+            "blank": total.blank,
+        },
+        "items": items,
+    })
+}
+
+/// Print per-language code statistics as CSV rows
+fn print_language_csv(entries: &[TreeEntry], top: usize) -> Result<()> {
+    let mut summaries = summarize_languages(entries);
+    summaries.truncate(top);
+    
+    println!("language,files,code,comments,blank");
+    for summary in &summaries {
+        println!(
+            "{},{},{},{},{}",
+            csv_field(&summary.language), summary.files, summary.code, summary.comments, summary.blank
+        );
+    }
+    
+    Ok(())
+}
+
+/// Top items of a distribution, largest first, and their total
+///
+/// Percentages are shares of this total, so they add up to 100%.
+fn top_distribution(
+    entries: &[TreeEntry],
+    dist_type: &DistributionType,
+    top: usize,
+    metric: DistributionMetric,
+    opts: &FormatOptions,
+) -> (Vec<(String, u64)>, u64) {
+    let distribution = calculate_distribution(entries, dist_type, metric, opts);
+    
+    // Sort by value descending and take top N
     let mut sorted: Vec<_> = distribution.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1));
+    sorted.sort_by_key(|(_, value)| std::cmp::Reverse(*value));
     sorted.truncate(top);
     
     // Calculate total for percentages
-    let total: u64 = sorted.iter().map(|(_, size)| size).sum();
-    
-    match format {
-        DistributionFormat::Table => print_distribution_table(&sorted, total, opts),
-        DistributionFormat::Chart => print_distribution_chart(&sorted, total, opts),
-    }
+    let total = sorted.iter().map(|(_, value)| value).sum();
+    (sorted, total)
 }
 
-/// Calculate size distribution
+/// Name of an option value as given on the command line
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value.to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+/// Calculate a distribution of files, adding up the given metric
 fn calculate_distribution(
     entries: &[TreeEntry],
     dist_type: &DistributionType,
+    metric: DistributionMetric,
+    opts: &FormatOptions,
 ) -> HashMap<String, u64> {
     let mut dist = HashMap::new();
+    let now = SystemTime::now();
     
-    fn process_entry(
-        entry: &TreeEntry,
-        dist: &mut HashMap<String, u64>,
-        dist_type: &DistributionType,
-    ) {
+    // Each entry is paired with the top-level directory it sits in
+    let mut stack: Vec<(&TreeEntry, Option<&str>)> = entries.iter().map(|entry| (entry, None)).collect();
+    
+    while let Some((entry, top_dir)) = stack.pop() {
         if !entry.is_dir {
             let key = match dist_type {
                 DistributionType::Type => {
-                    // Determine file type by extension
-                    let ext = Path::new(&entry.name)
-                        .extension()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("no extension");
-                    
-                    match ext.to_lowercase().as_str() {
-                        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" => "Images",
-                        "mp4" | "avi" | "mkv" | "mov" | "wmv" => "Videos",
-                        "mp3" | "wav" | "flac" | "aac" | "ogg" => "Audio",
-                        "zip" | "tar" | "gz" | "7z" | "rar" => "Archives",
-                        "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" => "Documents",
-                        "rs" | "js" | "ts" | "py" | "go" | "c" | "cpp" | "java" => "Code",
-                        "txt" | "md" | "log" => "Text",
-                        _ => "Other",
-                    }.to_string()
-                }
-                DistributionType::Size => {
-                    // Size buckets
-                    match entry.size {
-                        0..=1024 => "< 1KB",
-                        1025..=1_048_576 => "1KB - 1MB",
-                        1_048_577..=10_485_760 => "1MB - 10MB",
-                        10_485_761..=104_857_600 => "10MB - 100MB",
-                        104_857_601..=1_073_741_824 => "100MB - 1GB",
-                        _ => "> 1GB",
-                    }.to_string()
+                    // Prefer the walker's classification, which sniffs magic bytes
+                    entry.kind.clone()
+                        .unwrap_or_else(|| opts.classifier.classify_name(&entry.path))
                 }
+                DistributionType::Size => size_bucket(entry.size, &opts.size_format),
                 DistributionType::Ext => {
                     // By extension
                     Path::new(&entry.name)
@@ -359,39 +1428,88 @@ fn calculate_distribution(
                         .unwrap_or("no extension")
                         .to_string()
                 }
+                DistributionType::Language => {
+                    entry.code_stats.as_ref()
+                        .map_or_else(|| "Other".to_string(), |stats| stats.language.clone())
+                }
+                // Files directly in the root are grouped under "."
+                DistributionType::Dir => top_dir.map_or_else(|| ".".to_string(), |dir| format!("{}/", dir)),
+                DistributionType::Age => age_bucket(entry.modified, now).to_string(),
+                DistributionType::Owner => {
+                    entry.metadata.as_ref()
+                        .map_or_else(|| "unknown".to_string(), |meta| user_name(meta.uid))
+                }
+                DistributionType::Depth => format!("depth {}", entry.depth),
             };
             
-            *dist.entry(key).or_insert(0) += entry.size;
+            *dist.entry(key).or_insert(0) += metric.value(entry);
         }
         
         for child in &entry.children {
-            process_entry(child, dist, dist_type);
+            // Directories directly below the root start a group
+            let top_dir = top_dir.or(child.is_dir.then_some(child.name.as_str()));
+            stack.push((child, top_dir));
         }
     }
     
-    for entry in entries {
-        process_entry(entry, &mut dist, dist_type);
+    dist
+}
+
+/// Age bucket of a file from its modification time
+fn age_bucket(modified: SystemTime, now: SystemTime) -> &'static str {
+    const DAY: u64 = 86_400;
+    let age = now.duration_since(modified).map_or(0, |age| age.as_secs());
+    
+    if age < DAY {
+        "< 1d"
+    } else if age < 7 * DAY {
+        "< 1w"
+    } else if age < 30 * DAY {
+        "< 1mo"
+    } else if age < 365 * DAY {
+        "< 1y"
+    } else {
+        "older"
     }
+}
+
+/// Size bucket of a file, with boundaries in the display unit base
+fn size_bucket(size: u64, format: &SizeFormat) -> String {
+    let base = format.units.base();
+    let (kilo, mega, giga) = (base, base.pow(2), base.pow(3));
+    let label = |count, power| format.label(count, power);
     
-    dist
+    if size <= kilo {
+        format!("< {}", label(1, 1))
+    } else if size <= mega {
+        format!("{} - {}", label(1, 1), label(1, 2))
+    } else if size <= 10 * mega {
+        format!("{} - {}", label(1, 2), label(10, 2))
+    } else if size <= 100 * mega {
+        format!("{} - {}", label(10, 2), label(100, 2))
+    } else if size <= giga {
+        format!("{} - {}", label(100, 2), label(1, 3))
+    } else {
+        format!("> {}", label(1, 3))
+    }
 }
 
 /// Print distribution as a table
 fn print_distribution_table(
     data: &[(String, u64)],
     total: u64,
+    metric: DistributionMetric,
     opts: &FormatOptions,
 ) -> Result<()> {
-    println!("\n{:>15} {:>12} {:>8}", "Category", "Size", "Percent");
+    println!("\n{:>15} {:>12} {:>8}", "Category", metric.heading(), "Percent");
     println!("{}", "-".repeat(40));
     
-    for (category, size) in data {
-        let percent = (*size as f64 / total as f64) * 100.0;
+    for (category, value) in data {
         let line = format!(
             "{:>15} {:>12} {:>7.1}%",
             category,
-            format_size(*size),
-            percent
+            metric.format(*value, opts),
+            percent(*value, total)
         );
         
         if opts.color {
@@ -402,7 +1520,47 @@ fn print_distribution_table(
     }
     
     println!("{}", "-".repeat(40));
-    println!("{:>15} {:>12} {:>7.1}%", "Total", format_size(total), 100.0);
+    println!("{:>15} {:>12} {:>7.1}%", "Total", metric.format(total, opts), 100.0);
+    
+    Ok(())
+}
+
+/// Print per-language code statistics as a table
+fn print_language_table(entries: &[TreeEntry], top: usize, opts: &FormatOptions) -> Result<()> {
+    let mut summaries = summarize_languages(entries);
+    summaries.truncate(top);
+    
+    let header = format!(
+        "{:>15} {:>8} {:>10} {:>10} {:>10}",
+        "Language", "Files", "Code", "Comments", "Blank"
+    );
+    println!("\n{}", if opts.color { header.bold().to_string() } else { header });
+    println!("{}", "-".repeat(57));
+    
+    let mut total = LanguageSummary::default();
+    for summary in &summaries {
+        let line = format!(
+            "{:>15} {:>8} {:>10} {:>10} {:>10}",
+            summary.language, summary.files, summary.code, summary.comments, summary.blank
+        );
+        
+        if opts.color {
+            println!("{}", line.bright_white());
+        } else {
+            println!("{}", line);
+        }
+        
+        total.files += summary.files;
+        total.code += summary.code;
+        total.comments += summary.comments;
+        total.blank += summary.blank;
+    }
+    
+    println!("{}", "-".repeat(57));
+    println!(
+        "{:>15} {:>8} {:>10} {:>10} {:>10}",
+        "Total", total.files, total.code, total.comments, total.blank
+    );
     
     Ok(())
 }
@@ -411,9 +1569,10 @@ fn print_distribution_table(
 fn print_distribution_chart(
     data: &[(String, u64)],
     total: u64,
+    metric: DistributionMetric,
     opts: &FormatOptions,
 ) -> Result<()> {
-    println!("\n{}", "Size Distribution".bold());
+    println!("\n{}", format!("{} Distribution", metric.heading()).bold());
     println!();
     
     // Calculate max width for bars (terminal width - space for labels)
@@ -421,15 +1580,15 @@ fn print_distribution_chart(
     let bar_char = if opts.unicode { "█" } else { "#" };
     let empty_char = if opts.unicode { "░" } else { "-" };
     
-    for (category, size) in data {
-        let percent = (*size as f64 / total as f64) * 100.0;
+    for (category, value) in data {
+        let percent = percent(*value, total);
         let bar_width = ((percent / 100.0) * term_width as f64) as usize;
         let empty_width = term_width.saturating_sub(bar_width);
         
         // Format label
         let label = format!("{:>12}", category);
         let percent_str = format!("{:>5.1}%", percent);
-        let size_str = format_size(*size);
+        let size_str = metric.format(*value, opts);
         
         // Create bar
         let bar = bar_char.repeat(bar_width);
@@ -461,28 +1620,38 @@ fn print_distribution_chart(
         "Total".bold(), 
         "100.0%".dimmed(),
         " ".repeat(term_width + 2),
-        format_size(total).bright_white().bold()
+        metric.format(total, opts).bright_white().bold()
     );
     
     Ok(())
 }
 
-/// Format size in human-readable format
+/// Format code statistics for inline display
+pub fn format_code_stats(stats: &CodeStats) -> String {
+    format!(
+        "{}: {} code, {} comments, {} blank",
+        stats.language, stats.code, stats.comments, stats.blank
+    )
+}
+
+/// Summary for a collapsed directory, e.g. `1204 files, 8.1 MiB`
+pub fn format_collapsed(files: u64, size: u64, size_format: &SizeFormat) -> String {
+    format!(
+        "{} {}, {}",
+        files,
+        if files == 1 { "file" } else { "files" },
+        size_format.format(size)
+    )
+}
+
+/// Note for a directory left unopened by `--filelimit`, as GNU tree writes it
+pub fn format_unopened(count: usize) -> String {
+    format!("[{} entries exceeds filelimit, not opened]", count)
+}
+
+/// Format size in human-readable format, in the default IEC units
 pub fn format_size(size: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    let mut size = size as f64;
-    let mut unit_idx = 0;
-    
-    while size >= 1024.0 && unit_idx < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_idx += 1;
-    }
-    
-    if unit_idx == 0 {
-        format!("{} {}", size as u64, UNITS[unit_idx])
-    } else {
-        format!("{:.1} {}", size, UNITS[unit_idx])
-    }
+    SizeFormat::default().format(size)
 }
 
 /// Get terminal width
@@ -491,6 +1660,12 @@ fn terminal_width() -> usize {
     term_size::dimensions().map(|(w, _)| w).unwrap_or(80)
 }
 
+/// Get terminal height
+fn terminal_height() -> usize {
+    // Default to a classic 24-line terminal if unavailable
+    term_size::dimensions().map(|(_, h)| h).unwrap_or(24)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -499,9 +1674,244 @@ mod tests {
     fn test_format_size() {
         assert_eq!(format_size(0), "0 B");
         assert_eq!(format_size(1023), "1023 B");
-        assert_eq!(format_size(1024), "1.0 KB");
-        assert_eq!(format_size(1536), "1.5 KB");
-        assert_eq!(format_size(1_048_576), "1.0 MB");
-        assert_eq!(format_size(1_073_741_824), "1.0 GB");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(1_048_576), "1.0 MiB");
+        assert_eq!(format_size(1_073_741_824), "1.0 GiB");
+    }
+    
+    #[test]
+    fn test_size_buckets() {
+        let iec = SizeFormat::default();
+        assert_eq!(size_bucket(1024, &iec), "< 1 KiB");
+        assert_eq!(size_bucket(1025, &iec), "1 KiB - 1 MiB");
+        assert_eq!(size_bucket(5 * 1_048_576, &iec), "1 MiB - 10 MiB");
+        assert_eq!(size_bucket(2 << 30, &iec), "> 1 GiB");
+        
+        let si = SizeFormat { units: crate::units::SizeUnits::Si, precision: 1 };
+        assert_eq!(size_bucket(1000, &si), "< 1 kB");
+        assert_eq!(size_bucket(1024, &si), "1 kB - 1 MB");
+        assert_eq!(size_bucket(50_000_000, &si), "10 MB - 100 MB");
+    }
+    
+    #[test]
+    fn test_age_buckets() {
+        let now = SystemTime::now();
+        let ago = |secs| now - std::time::Duration::from_secs(secs);
+        assert_eq!(age_bucket(ago(60), now), "< 1d");
+        assert_eq!(age_bucket(ago(3 * 86_400), now), "< 1w");
+        assert_eq!(age_bucket(ago(20 * 86_400), now), "< 1mo");
+        assert_eq!(age_bucket(ago(200 * 86_400), now), "< 1y");
+        assert_eq!(age_bucket(ago(400 * 86_400), now), "older");
+        assert_eq!(age_bucket(now + std::time::Duration::from_secs(60), now), "< 1d");
+    }
+    
+    #[test]
+    fn test_distribution_by_dir_and_depth() {
+        let tree = vec![entry("root", 0, vec![
+            entry("root/README.md", 5, vec![]),
+            entry("root/src", 0, vec![
+                entry("root/src/main.rs", 20, vec![]),
+                entry("root/src/bin", 0, vec![entry("root/src/bin/cli.rs", 30, vec![])]),
+            ]),
+        ])];
+        let opts = FormatOptions::from_args_and_config(&Args::default(), &Config::default(), Default::default());
+        
+        let by_dir = calculate_distribution(&tree, &DistributionType::Dir, DistributionMetric::Bytes, &opts);
+        assert_eq!(by_dir.get("src/"), Some(&50));
+        assert_eq!(by_dir.get("."), Some(&5));
+        
+        let by_depth = calculate_distribution(&tree, &DistributionType::Depth, DistributionMetric::Files, &opts);
+        assert_eq!(by_depth.get("depth 1"), Some(&1));
+        assert_eq!(by_depth.get("depth 2"), Some(&1));
+        assert_eq!(by_depth.get("depth 3"), Some(&1));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_name_budget_leaves_room_for_icons() {
+        let mut opts = FormatOptions::from_args_and_config(&Args::default(), &Config::default(), Default::default());
+        let layout = ColumnLayout {
+            columns: long_columns,
+            widths: vec![10, 5],
+            term_width: Some(60),
+        };
+        
+        // 60 columns less the details, two separators, a space and the graph
+        let plain = layout.name_budget(2, &opts).unwrap();
+        assert_eq!(plain, 60 - 18 - 2 * opts.tree_chars.width());
+        
+        opts.icons = Some(Icons::default());
+        let with_icons = layout.name_budget(2, &opts).unwrap();
+        assert_eq!(plain - with_icons, Icons::default().width());
+    }
+    
+    #[test]
+    fn test_format_mode() {
+        assert_eq!(format_mode(0o040755), "drwxr-xr-x");
+        assert_eq!(format_mode(0o100644), "-rw-r--r--");
+        assert_eq!(format_mode(0o104755), "-rwsr-xr-x");
+        assert_eq!(format_mode(0o041777), "drwxrwxrwt");
+        assert_eq!(format_mode(0o120777), "lrwxrwxrwx");
+        assert_eq!(format_mode(0o102644), "-rw-r-Sr--");
+    }
+    
+    #[test]
+    fn test_display_width_and_truncation() {
+        assert_eq!(display_width("main.rs"), 7);
+        assert_eq!(display_width("日本語.txt"), 10);
+        assert_eq!(display_width("\u{e7a8} a"), 3);
+        
+        assert_eq!(truncate_to_width("main.rs", None), ("main.rs", false));
+        assert_eq!(truncate_to_width("main.rs", Some(7)), ("main.rs", false));
+        assert_eq!(truncate_to_width("main.rs", Some(5)), ("main", true));
+        assert_eq!(truncate_to_width("日本語.txt", Some(5)), ("日本", true));
+    }
+    
+    /// Tree entry for tests, with paths built from names
+    fn entry(path: &str, size: u64, children: Vec<TreeEntry>) -> TreeEntry {
+        let depth = path.matches('/').count();
+        TreeEntry {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: PathBuf::from(path),
+            size,
+            line_count: 0,
+            file_count: 0,
+            collapsed: false,
+            unopened_entries: None,
+            modified: SystemTime::UNIX_EPOCH,
+            is_dir: !path.contains('.'),
+            is_symlink: false,
+            is_executable: false,
+            indicator: crate::colors::Indicator::File,
+            children,
+            depth,
+            matches: None,
+            fuzzy: None,
+            code_stats: None,
+            kind: None,
+            mime: None,
+            metadata: None,
+        }
+    }
+    
+    #[test]
+    fn test_compact_dir_chains() {
+        let tree = vec![entry("root", 30, vec![
+            entry("root/src", 20, vec![
+                entry("root/src/main", 20, vec![
+                    entry("root/src/main/java", 20, vec![entry("root/src/main/java/A.java", 20, vec![])]),
+                ]),
+            ]),
+            entry("root/docs", 10, vec![
+                entry("root/docs/api", 0, vec![]),
+                entry("root/docs/guide.md", 10, vec![]),
+            ]),
+        ])];
+        
+        let compacted = compact_dir_chains(tree);
+        let root = &compacted[0];
+        assert_eq!(root.name, "root");
+        
+        let chain = &root.children[0];
+        assert_eq!(chain.name, "src/main/java/");
+        assert_eq!(chain.path, PathBuf::from("root/src/main/java"));
+        assert_eq!(chain.size, 20);
+        assert_eq!(chain.depth, 1);
+        assert_eq!(chain.children[0].name, "A.java");
+        assert_eq!(chain.children[0].depth, 2);
+        
+        // Directories with files or several children stay as they are
+        assert_eq!(root.children[1].name, "docs");
+        assert_eq!(root.children[1].children.len(), 2);
+    }
+    
+    #[test]
+    fn test_fit_to_lines() {
+        let tree = || vec![entry("root", 0, vec![
+            entry("root/small", 0, vec![entry("root/small/a.txt", 5, vec![])]),
+            entry("root/big", 0, vec![
+                entry("root/big/1.txt", 10, vec![]),
+                entry("root/big/2.txt", 10, vec![]),
+                entry("root/big/deep", 0, vec![entry("root/big/deep/3.txt", 10, vec![])]),
+            ]),
+        ])];
+        
+        // Only the root fits: both directories are summarised
+        let mut fitted = tree();
+        fit_to_lines(&mut fitted, 3);
+        let big = &fitted[0].children[1];
+        assert!(big.collapsed);
+        assert!(big.children.is_empty());
+        assert_eq!((big.file_count, big.size), (3, 30));
+        assert!(fitted[0].children[0].collapsed);
+        
+        // The directory with the most files is opened first
+        let mut fitted = tree();
+        fit_to_lines(&mut fitted, 6);
+        assert!(fitted[0].children[0].collapsed);
+        let big = &fitted[0].children[1];
+        assert!(!big.collapsed);
+        assert_eq!(big.children.len(), 3);
+        assert!(big.children[2].collapsed);
+        
+        // Everything fits
+        let mut fitted = tree();
+        fit_to_lines(&mut fitted, 8);
+        assert!(!fitted[0].children[0].collapsed);
+        assert!(!fitted[0].children[1].children[2].collapsed);
+        
+        // Collapsed and unopened directories count with their own totals
+        let mut modules = entry("root/web/node_modules", 50, vec![]);
+        modules.collapsed = true;
+        modules.file_count = 5;
+        let mut logs = entry("root/web/logs", 0, vec![]);
+        logs.unopened_entries = Some(40);
+        let web = entry("root/web", 0, vec![modules, logs, entry("root/web/index.js", 2, vec![])]);
+        assert_eq!(subtree_totals(&web), (46, 52));
+    }
+    
+    #[test]
+    fn test_tree_chars() {
+        let ascii = TreeChars::new(Charset::Ascii);
+        assert_eq!(ascii.graph(&[], false), "|-- ");
+        assert_eq!(ascii.graph(&[false, true], true), "|       `-- ");
+        
+        let rounded = TreeChars::new(Charset::Rounded);
+        assert_eq!(rounded.graph(&[false], true), "│   ╰── ");
+        
+        let compact = TreeChars::new(Charset::Compact);
+        assert_eq!(compact.graph(&[false, true], false), "│   ├ ");
+        assert_eq!(compact.width(), 2);
+        assert_eq!(TreeChars::new(Charset::Heavy).width(), 4);
+    }
+    
+    #[test]
+    fn test_path_display() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let canonical = root.canonicalize().unwrap();
+        let file = canonical.join("src/main.rs");
+        
+        let paths = PathDisplay::new(root, PathStyle::Name, None);
+        assert_eq!(paths.root_label(), root.display().to_string());
+        assert_eq!(paths.tree_name("main.rs", &file, 2), "main.rs");
+        assert_eq!(paths.full(&file), root.join("src/main.rs").display().to_string());
+        
+        let paths = PathDisplay::new(root, PathStyle::Relative, Some("proj".to_string()));
+        assert_eq!(paths.tree_name("root", &canonical, 0), "proj");
+        assert_eq!(paths.tree_name("main.rs", &file, 2), Path::new("proj/src/main.rs").display().to_string());
+        // Paths joined to the root as given resolve the same way
+        assert_eq!(paths.full(&root.join("src")), Path::new("proj/src").display().to_string());
+        
+        let paths = PathDisplay::new(root, PathStyle::Absolute, None);
+        assert_eq!(paths.root_label(), canonical.display().to_string());
+        assert_eq!(paths.tree_name("main.rs", &file, 2), file.display().to_string());
+    }
+    
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("2024-02-29 13:45"), "2024-02-29 13:45");
+        assert_eq!(csv_field("Feb 29, 2024"), "\"Feb 29, 2024\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,255 @@
+//! Nerd Font icons for tree entries
+//!
+//! Icons are chosen by directory name, then exact file name, then extension,
+//! falling back to generic file and folder glyphs. The built-in tables can be
+//! extended or overridden from the `[icons]` config section.
+
+use crate::config::IconConfig;
+use std::collections::HashMap;
+
+/// Icons for well-known directory names (lowercase)
+const DIR_ICONS: &[(&str, &str)] = &[
+    (".git", "\u{e5fb}"),
+    (".github", "\u{e5fd}"),
+    (".config", "\u{e5fc}"),
+    (".vscode", "\u{e70c}"),
+    ("node_modules", "\u{e5fa}"),
+    ("src", "\u{f121}"),
+    ("lib", "\u{f121}"),
+    ("test", "\u{f0c3}"),
+    ("tests", "\u{f0c3}"),
+    ("benches", "\u{f0e4}"),
+    ("doc", "\u{f02d}"),
+    ("docs", "\u{f02d}"),
+    ("target", "\u{e7a8}"),
+    ("bin", "\u{f489}"),
+    ("assets", "\u{f03e}"),
+    ("images", "\u{f03e}"),
+    ("desktop", "\u{f108}"),
+    ("documents", "\u{f02d}"),
+    ("downloads", "\u{f019}"),
+    ("music", "\u{f001}"),
+    ("pictures", "\u{f03e}"),
+    ("videos", "\u{f03d}"),
+];
+
+/// Icons for well-known file names (lowercase)
+const FILE_ICONS: &[(&str, &str)] = &[
+    ("cargo.toml", "\u{e7a8}"),
+    ("cargo.lock", "\u{e7a8}"),
+    ("dockerfile", "\u{f308}"),
+    ("docker-compose.yml", "\u{f308}"),
+    ("docker-compose.yaml", "\u{f308}"),
+    ("makefile", "\u{e779}"),
+    ("package.json", "\u{e71e}"),
+    ("package-lock.json", "\u{e71e}"),
+    ("go.mod", "\u{e627}"),
+    ("go.sum", "\u{e627}"),
+    (".gitignore", "\u{f1d3}"),
+    (".gitattributes", "\u{f1d3}"),
+    (".gitmodules", "\u{f1d3}"),
+    (".editorconfig", "\u{e615}"),
+    ("license", "\u{f0a3}"),
+    ("license-mit", "\u{f0a3}"),
+    ("license-apache", "\u{f0a3}"),
+    ("readme", "\u{f405}"),
+    ("readme.md", "\u{f405}"),
+];
+
+/// Icons by extension (lowercase)
+const EXTENSION_ICONS: &[(&str, &str)] = &[
+    ("rs", "\u{e7a8}"),
+    ("py", "\u{e606}"),
+    ("js", "\u{e74e}"),
+    ("mjs", "\u{e74e}"),
+    ("ts", "\u{e628}"),
+    ("tsx", "\u{e7ba}"),
+    ("jsx", "\u{e7ba}"),
+    ("go", "\u{e627}"),
+    ("c", "\u{e61e}"),
+    ("h", "\u{f0fd}"),
+    ("cpp", "\u{e61d}"),
+    ("cc", "\u{e61d}"),
+    ("hpp", "\u{f0fd}"),
+    ("java", "\u{e738}"),
+    ("kt", "\u{e634}"),
+    ("swift", "\u{e755}"),
+    ("rb", "\u{e791}"),
+    ("php", "\u{e73d}"),
+    ("lua", "\u{e620}"),
+    ("hs", "\u{e61f}"),
+    ("vim", "\u{e62b}"),
+    ("sh", "\u{f489}"),
+    ("bash", "\u{f489}"),
+    ("zsh", "\u{f489}"),
+    ("html", "\u{e736}"),
+    ("css", "\u{e749}"),
+    ("scss", "\u{e749}"),
+    ("md", "\u{e609}"),
+    ("json", "\u{e60b}"),
+    ("toml", "\u{e615}"),
+    ("yaml", "\u{e615}"),
+    ("yml", "\u{e615}"),
+    ("ini", "\u{e615}"),
+    ("lock", "\u{f023}"),
+    ("txt", "\u{f15c}"),
+    ("log", "\u{f15c}"),
+    ("pdf", "\u{f1c1}"),
+    ("sql", "\u{f1c0}"),
+    ("db", "\u{f1c0}"),
+    ("sqlite", "\u{f1c0}"),
+    ("zip", "\u{f1c6}"),
+    ("tar", "\u{f1c6}"),
+    ("gz", "\u{f1c6}"),
+    ("xz", "\u{f1c6}"),
+    ("zst", "\u{f1c6}"),
+    ("7z", "\u{f1c6}"),
+    ("rar", "\u{f1c6}"),
+    ("png", "\u{f1c5}"),
+    ("jpg", "\u{f1c5}"),
+    ("jpeg", "\u{f1c5}"),
+    ("gif", "\u{f1c5}"),
+    ("svg", "\u{f1c5}"),
+    ("webp", "\u{f1c5}"),
+    ("ico", "\u{f1c5}"),
+    ("mp3", "\u{f1c7}"),
+    ("wav", "\u{f1c7}"),
+    ("flac", "\u{f1c7}"),
+    ("ogg", "\u{f1c7}"),
+    ("mp4", "\u{f1c8}"),
+    ("mkv", "\u{f1c8}"),
+    ("mov", "\u{f1c8}"),
+    ("webm", "\u{f1c8}"),
+    ("ttf", "\u{f031}"),
+    ("otf", "\u{f031}"),
+    ("woff", "\u{f031}"),
+    ("woff2", "\u{f031}"),
+];
+
+/// Generic icons
+const DIR_ICON: &str = "\u{f07b}";
+const FILE_ICON: &str = "\u{f15b}";
+const EXECUTABLE_ICON: &str = "\u{f489}";
+const SYMLINK_FILE_ICON: &str = "\u{f481}";
+const SYMLINK_DIR_ICON: &str = "\u{f482}";
+
+/// Icon lookup tables, built-ins merged with config overrides
+#[derive(Debug, Clone)]
+pub struct Icons {
+    dirs: HashMap<String, String>,
+    files: HashMap<String, String>,
+    extensions: HashMap<String, String>,
+    /// Spaces printed after each icon
+    spacing: usize,
+}
+
+impl Icons {
+    /// Build the icon tables with overrides from the config
+    pub fn new(config: &IconConfig) -> Self {
+        fn table(builtin: &[(&str, &str)]) -> HashMap<String, String> {
+            builtin.iter()
+                .map(|(name, icon)| (name.to_string(), icon.to_string()))
+                .collect()
+        }
+        
+        let mut icons = Self {
+            dirs: table(DIR_ICONS),
+            files: table(FILE_ICONS),
+            extensions: table(EXTENSION_ICONS),
+            spacing: config.spacing,
+        };
+        
+        for (name, icon) in &config.dirs {
+            icons.dirs.insert(name.to_lowercase(), icon.clone());
+        }
+        for (name, icon) in &config.files {
+            icons.files.insert(name.to_lowercase(), icon.clone());
+        }
+        for (ext, icon) in &config.extensions {
+            icons.extensions.insert(ext.trim_start_matches('.').to_lowercase(), icon.clone());
+        }
+        
+        icons
+    }
+    
+    /// Icon for an entry
+    pub fn icon(&self, name: &str, is_dir: bool, is_symlink: bool, is_executable: bool) -> &str {
+        let lower = name.to_lowercase();
+        
+        if is_dir {
+            return self.dirs.get(&lower).map_or(
+                if is_symlink { SYMLINK_DIR_ICON } else { DIR_ICON },
+                String::as_str,
+            );
+        }
+        
+        if let Some(icon) = self.files.get(&lower) {
+            return icon;
+        }
+        if let Some(icon) = lower.rsplit_once('.').and_then(|(_, ext)| self.extensions.get(ext)) {
+            return icon;
+        }
+        
+        if is_symlink {
+            SYMLINK_FILE_ICON
+        } else if is_executable {
+            EXECUTABLE_ICON
+        } else {
+            FILE_ICON
+        }
+    }
+    
+    /// Icon followed by its spacing, ready to print before a name
+    pub fn prefix(&self, name: &str, is_dir: bool, is_symlink: bool, is_executable: bool) -> String {
+        format!("{}{}", self.icon(name, is_dir, is_symlink, is_executable), " ".repeat(self.spacing))
+    }
+    
+    /// Terminal columns taken by an icon prefix
+    ///
+    /// Nerd Font glyphs are drawn one column wide; the spacing is what keeps
+    /// names from overlapping on terminals that draw them wider.
+    pub fn width(&self) -> usize {
+        1 + self.spacing
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self::new(&IconConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_icon_lookup() {
+        let icons = Icons::default();
+        assert_eq!(icons.icon(".git", true, false, false), "\u{e5fb}");
+        assert_eq!(icons.icon("Docs", true, false, false), "\u{f02d}");
+        assert_eq!(icons.icon("misc", true, false, false), DIR_ICON);
+        assert_eq!(icons.icon("Cargo.toml", false, false, false), "\u{e7a8}");
+        assert_eq!(icons.icon("Dockerfile", false, false, false), "\u{f308}");
+        assert_eq!(icons.icon("main.RS", false, false, false), "\u{e7a8}");
+        assert_eq!(icons.icon("run", false, false, true), EXECUTABLE_ICON);
+        assert_eq!(icons.icon("data.bin", false, false, false), FILE_ICON);
+        assert_eq!(icons.prefix("a.rs", false, false, false), "\u{e7a8} ");
+        assert_eq!(icons.width(), 2);
+    }
+    
+    #[test]
+    fn test_icon_overrides() {
+        let mut config = IconConfig { spacing: 2, ..Default::default() };
+        config.extensions.insert(".rs".to_string(), "R".to_string());
+        config.files.insert("Justfile".to_string(), "J".to_string());
+        config.dirs.insert(".cache".to_string(), "C".to_string());
+        let icons = Icons::new(&config);
+        
+        assert_eq!(icons.icon("lib.rs", false, false, false), "R");
+        assert_eq!(icons.icon("justfile", false, false, false), "J");
+        assert_eq!(icons.icon(".cache", true, false, false), "C");
+        assert_eq!(icons.prefix("lib.rs", false, false, false), "R  ");
+        assert_eq!(icons.width(), 3);
+    }
+}
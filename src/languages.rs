@@ -0,0 +1,400 @@
+//! Language detection and code statistics
+//!
+//! This module detects the programming language of a file from its name,
+//! extension or shebang line and splits its lines into code, comment and
+//! blank lines using per-language comment syntax, similar to tokei or cloc.
+//! String literals are not parsed, so comment markers inside strings are
+//! treated as comments starting there (the line still counts as code).
+
+use crate::stats::is_binary_file;
+use crate::{Result, TreeEntry};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Comment syntax and detection rules for a language
+#[derive(Debug)]
+pub struct Language {
+    /// Display name
+    pub name: &'static str,
+    /// File extensions (lowercase, without the dot)
+    pub extensions: &'static [&'static str],
+    /// Exact file names (e.g. `Makefile`)
+    pub filenames: &'static [&'static str],
+    /// Interpreter names matched against a `#!` line
+    pub interpreters: &'static [&'static str],
+    /// Line comment markers
+    pub line_comments: &'static [&'static str],
+    /// Block comment start and end markers
+    pub block_comments: &'static [(&'static str, &'static str)],
+}
+
+const C_LINE: &[&str] = &["//"];
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+const HASH: &[&str] = &["#"];
+const NONE: &[&str] = &[];
+const NO_BLOCK: &[(&str, &str)] = &[];
+const XML_BLOCK: &[(&str, &str)] = &[("<!--", "-->")];
+
+/// Known languages
+pub static LANGUAGES: &[Language] = &[
+    Language { name: "Rust", extensions: &["rs"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "C", extensions: &["c", "h"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "C++", extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "C#", extensions: &["cs"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Go", extensions: &["go"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Java", extensions: &["java"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Kotlin", extensions: &["kt", "kts"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Scala", extensions: &["scala", "sc"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Swift", extensions: &["swift"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Dart", extensions: &["dart"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Zig", extensions: &["zig"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: NO_BLOCK },
+    Language { name: "JavaScript", extensions: &["js", "mjs", "cjs", "jsx"], filenames: &[], interpreters: &["node"], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "TypeScript", extensions: &["ts", "mts", "cts", "tsx"], filenames: &[], interpreters: &["deno", "ts-node"], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "PHP", extensions: &["php"], filenames: &[], interpreters: &["php"], line_comments: &["//", "#"], block_comments: C_BLOCK },
+    Language { name: "CSS", extensions: &["css"], filenames: &[], interpreters: &[], line_comments: NONE, block_comments: C_BLOCK },
+    Language { name: "SCSS", extensions: &["scss", "sass", "less"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Protobuf", extensions: &["proto"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK },
+    Language { name: "Python", extensions: &["py", "pyi", "pyw"], filenames: &[], interpreters: &["python", "python2", "python3"], line_comments: HASH, block_comments: &[("\"\"\"", "\"\"\""), ("'''", "'''")] },
+    Language { name: "Ruby", extensions: &["rb", "rake", "gemspec"], filenames: &["Rakefile", "Gemfile"], interpreters: &["ruby"], line_comments: HASH, block_comments: &[("=begin", "=end")] },
+    Language { name: "Perl", extensions: &["pl", "pm"], filenames: &[], interpreters: &["perl"], line_comments: HASH, block_comments: &[("=pod", "=cut")] },
+    Language { name: "Shell", extensions: &["sh", "bash", "zsh", "ksh"], filenames: &[".bashrc", ".zshrc", ".profile"], interpreters: &["sh", "bash", "zsh", "ksh", "dash"], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "Fish", extensions: &["fish"], filenames: &[], interpreters: &["fish"], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "PowerShell", extensions: &["ps1", "psm1"], filenames: &[], interpreters: &["pwsh"], line_comments: HASH, block_comments: &[("<#", "#>")] },
+    Language { name: "Makefile", extensions: &["mk", "mak"], filenames: &["Makefile", "makefile", "GNUmakefile"], interpreters: &["make"], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "CMake", extensions: &["cmake"], filenames: &["CMakeLists.txt"], interpreters: &[], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "Dockerfile", extensions: &["dockerfile"], filenames: &["Dockerfile", "Containerfile"], interpreters: &[], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "TOML", extensions: &["toml"], filenames: &["Cargo.lock"], interpreters: &[], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "YAML", extensions: &["yml", "yaml"], filenames: &[], interpreters: &[], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "JSON", extensions: &["json"], filenames: &[], interpreters: &[], line_comments: NONE, block_comments: NO_BLOCK },
+    Language { name: "INI", extensions: &["ini", "cfg", "conf"], filenames: &[], interpreters: &[], line_comments: &[";", "#"], block_comments: NO_BLOCK },
+    Language { name: "SQL", extensions: &["sql"], filenames: &[], interpreters: &[], line_comments: &["--"], block_comments: C_BLOCK },
+    Language { name: "Lua", extensions: &["lua"], filenames: &[], interpreters: &["lua"], line_comments: &["--"], block_comments: &[("--[[", "]]")] },
+    Language { name: "Haskell", extensions: &["hs", "lhs"], filenames: &[], interpreters: &["runhaskell"], line_comments: &["--"], block_comments: &[("{-", "-}")] },
+    Language { name: "Elixir", extensions: &["ex", "exs"], filenames: &[], interpreters: &["elixir"], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "Erlang", extensions: &["erl", "hrl"], filenames: &[], interpreters: &["escript"], line_comments: &["%"], block_comments: NO_BLOCK },
+    Language { name: "OCaml", extensions: &["ml", "mli"], filenames: &[], interpreters: &["ocaml"], line_comments: NONE, block_comments: &[("(*", "*)")] },
+    Language { name: "Clojure", extensions: &["clj", "cljs", "cljc", "edn"], filenames: &[], interpreters: &[], line_comments: &[";"], block_comments: NO_BLOCK },
+    Language { name: "Lisp", extensions: &["lisp", "el", "scm", "rkt"], filenames: &[], interpreters: &["sbcl", "guile", "racket"], line_comments: &[";"], block_comments: &[("#|", "|#")] },
+    Language { name: "R", extensions: &["r"], filenames: &[], interpreters: &["Rscript"], line_comments: HASH, block_comments: NO_BLOCK },
+    Language { name: "Julia", extensions: &["jl"], filenames: &[], interpreters: &["julia"], line_comments: HASH, block_comments: &[("#=", "=#")] },
+    Language { name: "Nix", extensions: &["nix"], filenames: &[], interpreters: &[], line_comments: HASH, block_comments: C_BLOCK },
+    Language { name: "HTML", extensions: &["html", "htm", "xhtml"], filenames: &[], interpreters: &[], line_comments: NONE, block_comments: XML_BLOCK },
+    Language { name: "XML", extensions: &["xml", "xsd", "xsl", "svg", "plist"], filenames: &[], interpreters: &[], line_comments: NONE, block_comments: XML_BLOCK },
+    Language { name: "Vue", extensions: &["vue"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: &[("<!--", "-->"), ("/*", "*/")] },
+    Language { name: "Markdown", extensions: &["md", "markdown"], filenames: &[], interpreters: &[], line_comments: NONE, block_comments: XML_BLOCK },
+    Language { name: "Text", extensions: &["txt", "text"], filenames: &[], interpreters: &[], line_comments: NONE, block_comments: NO_BLOCK },
+];
+
+/// Code, comment and blank line counts for a file or group of files
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeStats {
+    /// Detected language name
+    pub language: String,
+    /// Lines containing code
+    pub code: u64,
+    /// Lines containing only comments
+    pub comments: u64,
+    /// Blank lines
+    pub blank: u64,
+}
+
+impl CodeStats {
+    /// Total number of lines
+    pub fn lines(&self) -> u64 {
+        self.code + self.comments + self.blank
+    }
+}
+
+/// Detect the language of a file by name, extension, then shebang
+pub fn detect_language(path: &Path) -> Option<&'static Language> {
+    let name = path.file_name()?.to_str()?;
+    
+    if let Some(lang) = LANGUAGES.iter().find(|lang| lang.filenames.contains(&name)) {
+        return Some(lang);
+    }
+    
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        let ext = ext.to_lowercase();
+        if let Some(lang) = LANGUAGES.iter().find(|lang| lang.extensions.contains(&ext.as_str())) {
+            return Some(lang);
+        }
+        return None;
+    }
+    
+    // Extensionless scripts are identified by their interpreter
+    let interpreter = read_shebang(path)?;
+    LANGUAGES.iter().find(|lang| lang.interpreters.contains(&interpreter.as_str()))
+}
+
+/// Read the interpreter name from a `#!` line, following `env`
+fn read_shebang(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut buffer = [0u8; 128];
+    let n = file.read(&mut buffer).ok()?;
+    let head = std::str::from_utf8(buffer[..n].split(|&b| b == b'\n').next()?).ok()?;
+    let command = head.strip_prefix("#!")?;
+    
+    let mut parts = command.split_whitespace();
+    let mut program = parts.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skip env flags such as `-S`
+        program = parts.find(|part| !part.starts_with('-'))?;
+    }
+    
+    // Strip version suffixes like python3.11
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let program = if program.is_empty() { "sh" } else { program };
+    Some(program.to_string())
+}
+
+/// Count code, comment and blank lines in a file
+///
+/// Returns `None` for files of unknown language, binary files and files
+/// larger than `max_size`.
+pub fn count_code(path: &Path, max_size: u64) -> Result<Option<CodeStats>> {
+    let Some(lang) = detect_language(path) else {
+        return Ok(None);
+    };
+    
+    if std::fs::metadata(path)?.len() > max_size || is_binary_file(path)? {
+        return Ok(None);
+    }
+    
+    let reader = BufReader::new(File::open(path)?);
+    let mut stats = CodeStats {
+        language: lang.name.to_string(),
+        ..Default::default()
+    };
+    let mut block_end: Option<&str> = None;
+    
+    for line in reader.split(b'\n') {
+        let line = line?;
+        let line = String::from_utf8_lossy(&line);
+        match classify_line(&line, lang, &mut block_end) {
+            LineKind::Blank => stats.blank += 1,
+            LineKind::Comment => stats.comments += 1,
+            LineKind::Code => stats.code += 1,
+        }
+    }
+    
+    Ok(Some(stats))
+}
+
+/// Kind of a single source line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Comment,
+    Code,
+}
+
+/// Classify a line, tracking open block comments across lines
+fn classify_line<'a>(line: &str, lang: &'a Language, block_end: &mut Option<&'a str>) -> LineKind {
+    let mut rest = line.trim();
+    if rest.is_empty() {
+        return LineKind::Blank;
+    }
+    
+    let mut has_code = false;
+    let mut has_comment = false;
+    
+    loop {
+        // Inside a block comment: skip to its end marker
+        if let Some(end) = *block_end {
+            has_comment = true;
+            match rest.find(end) {
+                Some(idx) => {
+                    rest = &rest[idx + end.len()..];
+                    *block_end = None;
+                }
+                None => break,
+            }
+            continue;
+        }
+        
+        // Find the earliest comment marker on the rest of the line; a block
+        // opener starting at the same place wins, as in Lua's `--[[`
+        let line_comment = lang.line_comments.iter()
+            .filter_map(|marker| rest.find(marker))
+            .min();
+        let block_comment = lang.block_comments.iter()
+            .filter_map(|(start, end)| rest.find(start).map(|idx| (idx, *start, *end)))
+            .min_by_key(|(idx, _, _)| *idx);
+        
+        match (line_comment, block_comment) {
+            (Some(idx), block) if block.is_none_or(|(b, _, _)| idx < b) => {
+                has_code |= !rest[..idx].trim().is_empty();
+                has_comment = true;
+                break;
+            }
+            (_, Some((idx, start, end))) => {
+                has_code |= !rest[..idx].trim().is_empty();
+                rest = &rest[idx + start.len()..];
+                *block_end = Some(end);
+            }
+            _ => {
+                has_code |= !rest.trim().is_empty();
+                break;
+            }
+        }
+    }
+    
+    if has_code || !has_comment {
+        LineKind::Code
+    } else {
+        LineKind::Comment
+    }
+}
+
+/// Compute code statistics for every file in the tree in parallel
+pub fn apply_code_stats(entries: &mut [TreeEntry], max_size: u64) {
+    fn collect(entry: &TreeEntry, paths: &mut Vec<PathBuf>) {
+        if !entry.is_dir {
+            paths.push(entry.path.clone());
+        }
+        for child in &entry.children {
+            collect(child, paths);
+        }
+    }
+    
+    fn assign(entry: &mut TreeEntry, results: &mut HashMap<PathBuf, CodeStats>) {
+        if !entry.is_dir {
+            entry.code_stats = results.remove(&entry.path);
+        }
+        for child in &mut entry.children {
+            assign(child, results);
+        }
+    }
+    
+    let mut paths = Vec::new();
+    for entry in entries.iter() {
+        collect(entry, &mut paths);
+    }
+    
+    let mut results: HashMap<PathBuf, CodeStats> = paths
+        .into_par_iter()
+        .filter_map(|path| match count_code(&path, max_size) {
+            Ok(stats) => stats.map(|s| (path, s)),
+            Err(e) => {
+                log::debug!("Skipping code stats for {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    
+    for entry in entries.iter_mut() {
+        assign(entry, &mut results);
+    }
+}
+
+/// Aggregated statistics for one language
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageSummary {
+    /// Language name
+    pub language: String,
+    /// Number of files
+    pub files: u64,
+    /// Code lines
+    pub code: u64,
+    /// Comment lines
+    pub comments: u64,
+    /// Blank lines
+    pub blank: u64,
+}
+
+/// Aggregate code statistics per language, largest code count first
+pub fn summarize_languages(entries: &[TreeEntry]) -> Vec<LanguageSummary> {
+    fn add(entry: &TreeEntry, totals: &mut HashMap<String, LanguageSummary>) {
+        if let Some(stats) = &entry.code_stats {
+            let summary = totals.entry(stats.language.clone()).or_insert_with(|| LanguageSummary {
+                language: stats.language.clone(),
+                ..Default::default()
+            });
+            summary.files += 1;
+            summary.code += stats.code;
+            summary.comments += stats.comments;
+            summary.blank += stats.blank;
+        }
+        for child in &entry.children {
+            add(child, totals);
+        }
+    }
+    
+    let mut totals = HashMap::new();
+    for entry in entries {
+        add(entry, &mut totals);
+    }
+    
+    let mut summaries: Vec<LanguageSummary> = totals.into_values().collect();
+    summaries.sort_by(|a, b| b.code.cmp(&a.code).then_with(|| a.language.cmp(&b.language)));
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_detect_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        
+        fs::write(root.join("main.RS"), "").unwrap();
+        fs::write(root.join("Makefile"), "all:\n").unwrap();
+        fs::write(root.join("build"), "#!/usr/bin/env python3\nprint(1)\n").unwrap();
+        fs::write(root.join("run"), "#!/bin/bash\necho hi\n").unwrap();
+        fs::write(root.join("data"), "plain\n").unwrap();
+        
+        let name = |file: &str| detect_language(&root.join(file)).map(|lang| lang.name);
+        assert_eq!(name("main.RS"), Some("Rust"));
+        assert_eq!(name("Makefile"), Some("Makefile"));
+        assert_eq!(name("build"), Some("Python"));
+        assert_eq!(name("run"), Some("Shell"));
+        assert_eq!(name("data"), None);
+    }
+    
+    #[test]
+    fn test_count_code() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("lib.rs");
+        fs::write(&path, "\
+// Line comment
+/* Block
+   comment */
+
+fn add(a: i32, b: i32) -> i32 { // trailing comment
+    a + b /* inline */
+}
+").unwrap();
+        
+        let stats = count_code(&path, 1_000_000).unwrap().unwrap();
+        assert_eq!(stats.language, "Rust");
+        assert_eq!(stats.code, 3);
+        assert_eq!(stats.comments, 3);
+        assert_eq!(stats.blank, 1);
+        assert_eq!(stats.lines(), 7);
+    }
+    
+    #[test]
+    fn test_count_code_block_openers_sharing_line_markers() {
+        let temp_dir = TempDir::new().unwrap();
+        
+        let lua = temp_dir.path().join("init.lua");
+        fs::write(&lua, "--[[ Block\n  comment\n]]\n-- line\nprint(1)\n").unwrap();
+        let stats = count_code(&lua, 1_000_000).unwrap().unwrap();
+        assert_eq!(stats.language, "Lua");
+        assert_eq!((stats.code, stats.comments), (1, 4));
+        
+        let julia = temp_dir.path().join("main.jl");
+        fs::write(&julia, "#= Block\n  comment\n=#\n# line\nprintln(1)\n").unwrap();
+        let stats = count_code(&julia, 1_000_000).unwrap().unwrap();
+        assert_eq!(stats.language, "Julia");
+        assert_eq!((stats.code, stats.comments), (1, 4));
+    }
+}
@@ -14,22 +14,30 @@
 //! - **Configuration**: Support for .maram.toml config files
 //! - **Cross-platform**: Works on Linux, macOS, and Windows
 
+pub mod classify;
 pub mod cli;
+pub mod colors;
 pub mod config;
 pub mod error;
 pub mod filters;
 pub mod formatter;
+pub mod icons;
+pub mod languages;
+pub mod search;
 pub mod stats;
+pub mod units;
+pub mod users;
 pub mod walker;
 
 pub use cli::Args;
 pub use config::Config;
 pub use error::{Error, Result};
-pub use filters::{FilterOptions, SortBy};
-pub use formatter::{FormatOptions, OutputFormat};
+pub use filters::{FilterOptions, Grouping, SortBy, SortKey};
+pub use formatter::{DistributionMetric, DistributionOutput, DistributionType, FormatOptions, OutputFormat};
 pub use stats::{FileStats, TreeStats};
-pub use walker::{TreeEntry, Walker};
+pub use walker::{FileMetadata, TreeEntry, Walker};
 
+use stats::Largest;
 use std::path::Path;
 
 /// Main entry point for the maram tree visualization
@@ -56,42 +64,154 @@ use std::path::Path;
 /// ```
 pub fn run_tree(path: &Path, args: &Args, config: &Config) -> Result<()> {
     log::debug!("Starting tree traversal at: {:?}", path);
+    check_outputs(args)?;
     
     // Merge CLI args with config to get final options first
     let filter_opts = FilterOptions::from_args_and_config(args, config)?;
-    let format_opts = FormatOptions::from_args_and_config(args, config);
+    let format_opts = FormatOptions::from_args_and_config(args, config, filter_opts.classifier.clone());
+    
+    // Make `colored` follow --color/--no-color rather than its own tty check
+    colored::control::set_override(format_opts.color);
+    
+    // Reports that walk on their own can stand in for the tree; a JSON
+    // document always carries the summary and totals
+    let needs_tree = !args.no_tree || args.dist.is_some() || args.total_size
+        || args.output == OutputFormat::Json;
+    
+    // The largest items are ranked from the tree when it holds every entry,
+    // and only scanned separately otherwise
+    let wants_largest = args.largest.is_some() || args.largest_dirs.is_some();
+    let largest_from_tree = wants_largest && needs_tree && !filter_opts.limits_listing();
     
     // Check if we need buffered mode for advanced features
     let needs_buffering = matches!(args.output, OutputFormat::Json | OutputFormat::Csv)
         || args.dist.is_some()           // Distribution analysis
         || args.total_size                // Total size calculation
         || args.dir_sizes                 // Directory size calculation
-        || filter_opts.sort_by.is_some(); // Sorting required
+        || args.needs_extended_metadata() // Permissions, ownership, inodes
+        || args.date                      // Modification time column
+        || args.compact_dirs              // Collapsing directory chains
+        || args.fit.is_some()             // Fitting the tree to a line budget
+        || !filter_opts.sort_keys.is_empty() // Sorting required
+        || filter_opts.contains.is_some() // Content search
+        || filter_opts.fuzzy.is_some()    // Fuzzy ranking
+        || largest_from_tree;             // Ranking the largest items
+    
+    let largest_walker = (wants_largest && !largest_from_tree)
+        .then(|| Walker::new(path, filter_opts.clone(), args.threads))
+        .transpose()?;
+    
+    // JSON output collects the tree and every report into one document
+    let mut document = serde_json::Map::new();
     
     // Use streaming by default for better performance
-    if !needs_buffering {
-        log::debug!("Using streaming output for {:?} format", args.output);
-        
-        // Apply config defaults properly
-        // Unicode defaults to true from config, use args.unicode only if explicitly set
-        let unicode = args.unicode || config.display.unicode;
-        let show_size = args.show_size || config.display.show_size;
-        let show_lines = args.show_lines || config.display.show_lines;
-        
-        // Use streaming walker for direct output
-        let mut stream_walker = walker::StreamWalker::new(
-            filter_opts,
-            args.output,
-            show_size,
-            show_lines,
-            unicode,
-        );
-        
-        return stream_walker.stream(path);
-    }
-    
-    
-    // Buffered path for features that need the full tree
+    let mut entries = Vec::new();
+    if needs_tree && needs_buffering {
+        entries = buffered_tree(path, args, filter_opts, &format_opts, &mut document)?;
+    } else if needs_tree {
+        stream_tree(path, args, config, filter_opts, &format_opts)?;
+    }
+    
+    // Show the largest files and directories if requested
+    let (files, dirs) = (args.largest.unwrap_or(0), args.largest_dirs.unwrap_or(0));
+    let largest = if largest_from_tree {
+        Some(Largest::from_entries(&entries, files, dirs))
+    } else {
+        largest_walker.map(|walker| walker.largest(files, dirs))
+    };
+    if let Some(largest) = largest {
+        if args.output == OutputFormat::Json {
+            document.insert("largest".to_string(), formatter::largest_json(&largest, &format_opts));
+        } else {
+            formatter::print_largest(&largest, &format_opts)?;
+        }
+    }
+    
+    if args.output == OutputFormat::Json {
+        formatter::print_json_document(document)?;
+    }
+    
+    Ok(())
+}
+
+/// Reject output options that would be ignored or mix documents in one stream
+///
+/// `--fit` only reshapes the printed tree. A JSON or CSV distribution is a
+/// document of its own, so nothing else may be printed next to it; with
+/// `--output=json` it joins the JSON document.
+fn check_outputs(args: &Args) -> Result<()> {
+    if args.fit.is_some() && (args.output != OutputFormat::Tree || args.no_tree) {
+        return Err(Error::general("--fit only applies to the tree printed by --output=tree"));
+    }
+    
+    if args.dist.is_none() || args.dist_output == DistributionOutput::Text {
+        return Ok(());
+    }
+    
+    if args.output == OutputFormat::Json {
+        if args.dist_output == DistributionOutput::Csv {
+            return Err(Error::general("--dist-output=csv can't be combined with --output=json"));
+        }
+        return Ok(());
+    }
+    
+    if !args.no_tree {
+        return Err(Error::general("--dist-output=json and --dist-output=csv require --no-tree"));
+    }
+    if args.total_size || args.largest.is_some() || args.largest_dirs.is_some() {
+        return Err(Error::general(
+            "--dist-output=json and --dist-output=csv can't be combined with --total-size, --largest or --largest-dirs",
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Print the tree straight from the filesystem without buffering it
+fn stream_tree(
+    path: &Path,
+    args: &Args,
+    config: &Config,
+    filter_opts: FilterOptions,
+    format_opts: &FormatOptions,
+) -> Result<()> {
+    log::debug!("Using streaming output for {:?} format", args.output);
+    
+    // Apply config defaults properly
+    let show_size = args.show_size || config.display.show_size;
+    let show_lines = args.show_lines || config.display.show_lines;
+    
+    // Use streaming walker for direct output
+    let mut stream_walker = walker::StreamWalker::new(
+        filter_opts,
+        args.output,
+        show_size,
+        show_lines,
+        format_opts.tree_chars,
+    );
+    stream_walker.set_max_file_size(args.max_file_size);
+    stream_walker.set_colors(format_opts.color, format_opts.colors.clone());
+    stream_walker.set_icons(format_opts.icons.clone());
+    stream_walker.set_size_format(format_opts.size_format);
+    stream_walker.set_paths(format_opts.paths.clone());
+    if args.code_stats {
+        stream_walker.enable_code_stats();
+    }
+    
+    stream_walker.stream(path)
+}
+
+/// Build the full tree, then print it and the reports that need it
+///
+/// With JSON output they are added to `document` instead of being printed.
+/// The walked entries are returned for reports that follow the tree.
+fn buffered_tree(
+    path: &Path,
+    args: &Args,
+    filter_opts: FilterOptions,
+    format_opts: &FormatOptions,
+    document: &mut serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<TreeEntry>> {
     log::debug!("Using buffered walker for advanced features");
     
     // Create walker with options
@@ -99,34 +219,81 @@ pub fn run_tree(path: &Path, args: &Args, config: &Config) -> Result<()> {
     
     // Configure walker based on args
     walker.set_max_file_size(args.max_file_size);
-    if args.show_lines {
+    if args.show_lines || (args.dist.is_some() && args.dist_metric == Some(DistributionMetric::Lines)) {
         walker.enable_line_counting();
     }
     if args.dir_sizes {
         walker.enable_dir_sizes();
     }
+    if args.needs_extended_metadata() || args.dist == Some(DistributionType::Owner) {
+        walker.enable_extended_metadata();
+    }
+    if args.code_stats || args.dist == Some(DistributionType::Language) {
+        walker.enable_code_stats();
+    }
+    // Sniffing file headers is costly, so only classify when asked to
+    if args.file_kinds || args.dist == Some(DistributionType::Type) {
+        walker.enable_classification();
+    }
     
     // Perform traversal
     let entries = walker.walk()?;
     
     // Format and output results
     match args.output {
-        OutputFormat::Tree => formatter::print_tree(&entries, &format_opts)?,
-        OutputFormat::Json => formatter::print_json(&entries)?,
-        OutputFormat::Csv => formatter::print_csv(&entries)?,
-        OutputFormat::Plain => formatter::print_plain(&entries)?,
+        OutputFormat::Json => {
+            collect_json(&entries, args, format_opts, document)?;
+            return Ok(entries);
+        }
+        _ if args.no_tree => {}
+        OutputFormat::Tree => formatter::print_tree(&entries, format_opts)?,
+        OutputFormat::Csv => formatter::print_csv(&entries, format_opts)?,
+        OutputFormat::Plain if args.fuzzy.is_some() => formatter::print_ranked_plain(&entries, format_opts)?,
+        OutputFormat::Plain => formatter::print_plain(&entries, format_opts)?,
     }
     
     // Show total size if requested
     if args.total_size && matches!(args.output, OutputFormat::Tree) {
         let stats = TreeStats::from_entries(&entries);
-        formatter::print_total_size(&stats, &format_opts)?;
+        formatter::print_total_size(&stats, format_opts)?;
     }
     
     // Show size distribution if requested
     if let Some(dist_type) = &args.dist {
-        formatter::print_distribution(&entries, dist_type, args.top, &args.format, &format_opts)?;
+        match args.dist_output {
+            DistributionOutput::Text => {
+                formatter::print_distribution(&entries, dist_type, args.top, &args.format, args.dist_metric, format_opts)?
+            }
+            DistributionOutput::Json => {
+                formatter::print_distribution_json(&entries, dist_type, args.top, args.dist_metric, format_opts)?
+            }
+            DistributionOutput::Csv => {
+                formatter::print_distribution_csv(&entries, dist_type, args.top, args.dist_metric, format_opts)?
+            }
+        }
+    }
+    
+    Ok(entries)
+}
+
+/// Add the tree, summary counts, totals and distribution to the JSON document
+fn collect_json(
+    entries: &[TreeEntry],
+    args: &Args,
+    format_opts: &FormatOptions,
+    document: &mut serde_json::Map<String, serde_json::Value>,
+) -> Result<()> {
+    let stats = TreeStats::from_entries(entries);
+    if !args.no_tree {
+        document.insert("tree".to_string(), formatter::tree_json(entries, format_opts)?);
+    }
+    document.insert("summary".to_string(), formatter::summary_json(&stats));
+    document.insert("totals".to_string(), formatter::totals_json(&stats, format_opts));
+    
+    if let Some(dist_type) = &args.dist {
+        let distribution = formatter::distribution_json(entries, dist_type, args.top, args.dist_metric, format_opts);
+        document.insert("distribution".to_string(), distribution);
     }
     
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,454 @@
+//! Name, fuzzy and content search
+//!
+//! This module implements grep-style searching of file contents, fzf-style
+//! fuzzy ranking of paths, and helpers for highlighting `--search` matches
+//! in entry names. Files are scanned in
+//! parallel, binary files and files above the configured size limit are
+//! skipped, and matches are recorded per line so they can be shown inline in
+//! the tree or exported with line numbers in JSON.
+
+use crate::stats::is_binary_file;
+use crate::{Result, TreeEntry};
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of matching lines kept per file (the count stays exact)
+const MAX_STORED_LINES: usize = 1000;
+
+/// Maximum length of a stored matching line
+const MAX_LINE_LENGTH: usize = 512;
+
+/// Content matches found in a single file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentMatches {
+    /// Number of matching lines
+    pub count: u64,
+    /// Matching lines with their line numbers
+    pub lines: Vec<LineMatch>,
+}
+
+/// A single matching line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineMatch {
+    /// Line number (1-based)
+    pub line: u64,
+    /// Line content with trailing whitespace removed
+    pub text: String,
+}
+
+impl ContentMatches {
+    /// First matching line, if any
+    pub fn first(&self) -> Option<&LineMatch> {
+        self.lines.first()
+    }
+}
+
+/// Search a file's contents for a pattern
+///
+/// Returns `None` for files without matches, binary files and files larger
+/// than `max_size`.
+pub fn search_file(path: &Path, regex: &Regex, max_size: u64) -> Result<Option<ContentMatches>> {
+    let metadata = std::fs::metadata(path)?;
+    if metadata.len() > max_size {
+        log::debug!("Skipping content search for large file: {:?}", path);
+        return Ok(None);
+    }
+    
+    if is_binary_file(path)? {
+        log::debug!("Skipping content search for binary file: {:?}", path);
+        return Ok(None);
+    }
+    
+    let file = File::open(path)?;
+    let mut reader = BufReader::with_capacity(8192, file);
+    let mut buffer = Vec::new();
+    let mut matches = ContentMatches::default();
+    let mut line_number = 0u64;
+    
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+        line_number += 1;
+        
+        // Lossy decoding keeps mostly-UTF-8 files searchable
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.trim_end();
+        if !regex.is_match(line) {
+            continue;
+        }
+        
+        matches.count += 1;
+        if matches.lines.len() < MAX_STORED_LINES {
+            matches.lines.push(LineMatch {
+                line: line_number,
+                text: truncate_line(line),
+            });
+        }
+    }
+    
+    Ok((matches.count > 0).then_some(matches))
+}
+
+/// Search every file in the tree in parallel and keep only matching files
+/// and the directories leading to them
+pub fn apply_content_search(entries: &mut [TreeEntry], regex: &Regex, max_size: u64) {
+    let mut paths = Vec::new();
+    for entry in entries.iter() {
+        collect_file_paths(entry, &mut paths);
+    }
+    
+    let mut results: HashMap<PathBuf, ContentMatches> = paths
+        .par_iter()
+        .filter_map(|path| match search_file(path, regex, max_size) {
+            Ok(found) => found.map(|m| ((*path).clone(), m)),
+            Err(e) => {
+                log::warn!("Error searching {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    
+    retain_files(entries, &mut |entry| {
+        entry.matches = results.remove(&entry.path);
+        entry.matches.is_some()
+    });
+}
+
+/// Fuzzy match result for a single file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FuzzyMatch {
+    /// Match score (higher is better)
+    pub score: i64,
+    /// Byte offsets of matched characters within the entry name
+    pub positions: Vec<usize>,
+}
+
+impl FuzzyMatch {
+    /// Byte ranges of the name covered by matched characters
+    pub fn name_ranges(&self, name: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        
+        for &pos in &self.positions {
+            let Some(ch) = name.get(pos..).and_then(|rest| rest.chars().next()) else {
+                continue;
+            };
+            let end = pos + ch.len_utf8();
+            
+            // Merge runs of adjacent characters into one range
+            match ranges.last_mut() {
+                Some(last) if last.end == pos => last.end = end,
+                _ => ranges.push(pos..end),
+            }
+        }
+        
+        ranges
+    }
+}
+
+/// Score every file by fuzzy-matching its root-relative path, keeping only
+/// the `limit` best matches and the directories leading to them
+pub fn apply_fuzzy_search(entries: &mut [TreeEntry], query: &str, root: &Path, limit: usize) {
+    let mut paths = Vec::new();
+    for entry in entries.iter() {
+        collect_file_paths(entry, &mut paths);
+    }
+    
+    let mut scored: Vec<(PathBuf, String, i64, Vec<usize>)> = paths
+        .into_par_iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().into_owned();
+            let (score, positions) = fuzzy_match(query, &relative)?;
+            Some((path, relative, score, positions))
+        })
+        .collect();
+    
+    // Best score first, then shorter paths, then alphabetical for stable output
+    scored.sort_by(|a, b| {
+        b.2.cmp(&a.2)
+            .then_with(|| a.1.len().cmp(&b.1.len()))
+            .then_with(|| a.1.cmp(&b.1))
+    });
+    scored.truncate(limit);
+    
+    let mut results: HashMap<PathBuf, FuzzyMatch> = scored
+        .into_iter()
+        .map(|(path, relative, score, positions)| {
+            // Keep only positions inside the file name for highlighting
+            let name_start = relative.rfind('/').map_or(0, |idx| idx + 1);
+            let positions = positions
+                .into_iter()
+                .filter(|&pos| pos >= name_start)
+                .map(|pos| pos - name_start)
+                .collect();
+            (path, FuzzyMatch { score, positions })
+        })
+        .collect();
+    
+    retain_files(entries, &mut |entry| {
+        entry.fuzzy = results.remove(&entry.path);
+        entry.fuzzy.is_some()
+    });
+}
+
+/// Score for each matched character
+const SCORE_MATCH: i64 = 16;
+/// Penalty for starting a gap between matched characters
+const PENALTY_GAP_START: i64 = 3;
+/// Penalty for each additional character in a gap
+const PENALTY_GAP_EXTENSION: i64 = 1;
+/// Bonus for a match right after a path separator
+const BONUS_PATH_SEPARATOR: i64 = 10;
+/// Bonus for a match at the start of a word (after `_`, `-`, `.` or space)
+const BONUS_BOUNDARY: i64 = 8;
+/// Bonus for a camelCase transition
+const BONUS_CAMEL: i64 = 7;
+/// Bonus for each match directly following the previous one
+const BONUS_CONSECUTIVE: i64 = 4;
+/// Bonus for matches inside the file name rather than parent directories
+const BONUS_FILENAME: i64 = 2;
+
+/// Fuzzy-match a query against a candidate string, fzf style
+///
+/// The query must appear in the candidate as a subsequence. The shortest
+/// window containing it is located with a forward and a backward scan, then
+/// scored with bonuses for word boundaries and consecutive characters and
+/// penalties for gaps. Matching is case-insensitive unless the query contains
+/// an uppercase letter. Returns the score and the byte offsets of the matched
+/// characters in the candidate.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return None;
+    }
+    
+    let case_sensitive = query.iter().any(|c| c.is_uppercase());
+    let chars: Vec<(usize, char)> = candidate.char_indices().collect();
+    let eq = |a: char, b: char| {
+        if case_sensitive {
+            a == b
+        } else {
+            a.to_lowercase().eq(b.to_lowercase())
+        }
+    };
+    
+    // Forward scan: find where the first complete subsequence ends
+    let mut qi = 0;
+    let mut end = None;
+    for (ci, &(_, ch)) in chars.iter().enumerate() {
+        if eq(ch, query[qi]) {
+            qi += 1;
+            if qi == query.len() {
+                end = Some(ci);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    
+    // Backward scan: find the latest start that still contains the query
+    let mut qi = query.len();
+    let mut start = end;
+    for ci in (0..=end).rev() {
+        if eq(chars[ci].1, query[qi - 1]) {
+            qi -= 1;
+            if qi == 0 {
+                start = ci;
+                break;
+            }
+        }
+    }
+    
+    // Score the window with a greedy forward pass
+    let filename_start = candidate.rfind('/').map_or(0, |idx| idx + 1);
+    let mut score = 0;
+    let mut positions = Vec::with_capacity(query.len());
+    let mut qi = 0;
+    let mut last_match: Option<usize> = None;
+    let mut run_bonus = 0;
+    
+    for ci in start..=end {
+        let (offset, ch) = chars[ci];
+        if qi == query.len() || !eq(ch, query[qi]) {
+            continue;
+        }
+        
+        score += SCORE_MATCH;
+        
+        let prev = ci.checked_sub(1).map(|i| chars[i].1);
+        let mut bonus = match prev {
+            None | Some('/') | Some('\\') => BONUS_PATH_SEPARATOR,
+            Some('_') | Some('-') | Some('.') | Some(' ') => BONUS_BOUNDARY,
+            Some(p) if p.is_lowercase() && ch.is_uppercase() => BONUS_CAMEL,
+            _ => 0,
+        };
+        
+        match last_match {
+            Some(last) if last + 1 == ci => {
+                // A run keeps the bonus of the character that started it
+                bonus = bonus.max(run_bonus) + BONUS_CONSECUTIVE;
+            }
+            Some(last) => {
+                let gap = (ci - last - 1) as i64;
+                score -= PENALTY_GAP_START + (gap - 1) * PENALTY_GAP_EXTENSION;
+                run_bonus = bonus;
+            }
+            None => run_bonus = bonus,
+        }
+        score += bonus;
+        
+        if offset >= filename_start {
+            score += BONUS_FILENAME;
+        }
+        
+        last_match = Some(ci);
+        positions.push(offset);
+        qi += 1;
+    }
+    
+    Some((score, positions))
+}
+
+/// Collect paths of all files in a tree
+fn collect_file_paths(entry: &TreeEntry, paths: &mut Vec<PathBuf>) {
+    if entry.is_dir {
+        for child in &entry.children {
+            collect_file_paths(child, paths);
+        }
+    } else {
+        paths.push(entry.path.clone());
+    }
+}
+
+/// Keep files accepted by `keep` and the directories leading to them
+///
+/// Root entries are always kept so the tree still has a header line.
+fn retain_files(entries: &mut [TreeEntry], keep: &mut dyn FnMut(&mut TreeEntry) -> bool) {
+    for entry in entries.iter_mut() {
+        if entry.is_dir {
+            retain_children(&mut entry.children, keep);
+        } else {
+            keep(entry);
+        }
+    }
+}
+
+/// Recursive helper for `retain_files` that drops directories left empty
+fn retain_children(entries: &mut Vec<TreeEntry>, keep: &mut dyn FnMut(&mut TreeEntry) -> bool) {
+    entries.retain_mut(|entry| {
+        if entry.is_dir {
+            retain_children(&mut entry.children, keep);
+            !entry.children.is_empty()
+        } else {
+            keep(entry)
+        }
+    });
+}
+
+/// Byte ranges of `name` covered by search matches against the full path
+///
+/// `--search` matches the whole path string, so the regex match offsets are
+/// computed on the path and then clipped to the trailing file name.
+pub fn name_match_ranges(regex: &Regex, path: &Path, name: &str) -> Vec<Range<usize>> {
+    let path_str = path.to_string_lossy();
+    let Some(name_start) = path_str.len().checked_sub(name.len()) else {
+        return Vec::new();
+    };
+    if !path_str.ends_with(name) {
+        return Vec::new();
+    }
+    
+    regex
+        .find_iter(&path_str)
+        .filter(|m| m.end() > name_start)
+        .map(|m| m.start().max(name_start) - name_start..m.end() - name_start)
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// Truncate a line to a displayable length on a character boundary
+fn truncate_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_LENGTH) {
+        Some((idx, _)) => line[..idx].to_string(),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+    
+    #[test]
+    fn test_search_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "fn main() {{").unwrap();
+        writeln!(file, "    println!(\"hello\");").unwrap();
+        writeln!(file, "    println!(\"world\");").unwrap();
+        writeln!(file, "}}").unwrap();
+        file.flush().unwrap();
+        
+        let regex = Regex::new("println").unwrap();
+        let matches = search_file(file.path(), &regex, 1_000_000).unwrap().unwrap();
+        assert_eq!(matches.count, 2);
+        assert_eq!(matches.first().unwrap().line, 2);
+        assert_eq!(matches.lines[1].text, "    println!(\"world\");");
+        
+        let regex = Regex::new("missing").unwrap();
+        assert!(search_file(file.path(), &regex, 1_000_000).unwrap().is_none());
+        
+        // Files above the size limit are skipped
+        let regex = Regex::new("main").unwrap();
+        assert!(search_file(file.path(), &regex, 4).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_name_match_ranges() {
+        let path = Path::new("/project/src/main_test.rs");
+        
+        let regex = Regex::new("main").unwrap();
+        assert_eq!(name_match_ranges(&regex, path, "main_test.rs"), vec![0..4]);
+        
+        // Matches spanning the parent directory are clipped to the name
+        let regex = Regex::new("src/main").unwrap();
+        assert_eq!(name_match_ranges(&regex, path, "main_test.rs"), vec![0..4]);
+        
+        // Matches only in the parent directory highlight nothing
+        let regex = Regex::new("project").unwrap();
+        assert!(name_match_ranges(&regex, path, "main_test.rs").is_empty());
+    }
+    
+    #[test]
+    fn test_fuzzy_match() {
+        let (_, positions) = fuzzy_match("wlk", "src/walker.rs").unwrap();
+        assert_eq!(positions, vec![4, 6, 7]);
+        assert!(fuzzy_match("xyz", "src/walker.rs").is_none());
+        
+        // Smart case: an uppercase query only matches uppercase
+        assert!(fuzzy_match("W", "src/walker.rs").is_none());
+        assert!(fuzzy_match("w", "src/Walker.rs").is_some());
+        
+        // Boundary and consecutive matches beat scattered ones
+        let (tight, _) = fuzzy_match("main", "src/main.rs").unwrap();
+        let (loose, _) = fuzzy_match("main", "src/mod_a_input.rs").unwrap();
+        assert!(tight > loose);
+        
+        // The shortest window is scored, not the first occurrence
+        let (_, positions) = fuzzy_match("ab", "a__ab").unwrap();
+        assert_eq!(positions, vec![3, 4]);
+    }
+    
+    #[test]
+    fn test_fuzzy_name_ranges() {
+        let fuzzy = FuzzyMatch { score: 0, positions: vec![0, 1, 2, 5] };
+        assert_eq!(fuzzy.name_ranges("walker.rs"), vec![0..3, 5..6]);
+    }
+}
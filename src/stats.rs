@@ -6,11 +6,17 @@
 
 use crate::{Result, TreeEntry};
 use rayon::prelude::*;
-use std::fs::File;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::fs::{File, Metadata};
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Statistics for a file or directory
 #[derive(Debug, Clone, Default)]
@@ -126,7 +132,7 @@ pub fn count_lines_parallel(paths: &[&Path], max_size: u64) -> Vec<u64> {
 }
 
 /// Check if a file is likely binary by examining first bytes
-fn is_binary_file(path: &Path) -> Result<bool> {
+pub(crate) fn is_binary_file(path: &Path) -> Result<bool> {
     let mut file = File::open(path)?;
     let mut buffer = [0u8; 512];
     
@@ -145,7 +151,7 @@ fn is_binary_file(path: &Path) -> Result<bool> {
     let text_chars = buffer[..bytes_read]
         .iter()
         .filter(|&&b| {
-            b == b'\n' || b == b'\r' || b == b'\t' || (b >= 32 && b <= 126) || b >= 128
+            b == b'\n' || b == b'\r' || b == b'\t' || (32..=126).contains(&b) || b >= 128
         })
         .count();
     
@@ -193,14 +199,523 @@ fn calculate_dir_size_recursive(path: &Path, size: &Arc<AtomicU64>) -> Result<()
     Ok(())
 }
 
+/// Recursive totals for a directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirTotals {
+    /// Total size of all files below the directory
+    pub size: u64,
+    /// Number of files below the directory
+    pub files: u64,
+    /// Total line count of text files below the directory
+    pub lines: u64,
+}
+
+impl DirTotals {
+    /// Add another set of totals to this one
+    fn add(&mut self, other: &DirTotals) {
+        self.size += other.size;
+        self.files += other.files;
+        self.lines += other.lines;
+    }
+}
+
+/// Totals for every directory in a subtree, plus per-file line counts
+#[derive(Debug, Clone, Default)]
+pub struct SubtreeTotals {
+    /// Recursive totals keyed by directory path
+    pub dirs: HashMap<PathBuf, DirTotals>,
+    /// Line counts of the files that were counted, keyed by file path
+    pub file_lines: HashMap<PathBuf, u64>,
+}
+
+/// Calculate totals for a directory and every directory below it
+///
+/// Every directory in the subtree (including `path` itself) gets an entry so
+/// callers can cache the whole subtree from one pass. Lines are only counted
+/// when `count_lines_max` is set, using it as the maximum file size, and the
+/// per-file counts are returned too so files are never counted twice.
+/// Only entries accepted by `include` are counted or descended into, so the
+/// totals match what the tree shows. Unreadable directories contribute
+/// nothing.
+pub fn calculate_dir_totals<F>(path: &Path, count_lines_max: Option<u64>, include: &F) -> SubtreeTotals
+where
+    F: Fn(&Path, &Metadata) -> bool + Sync,
+{
+    let mut all = SubtreeTotals::default();
+    dir_totals_recursive(path, count_lines_max, include, &mut all);
+    all
+}
+
+/// Recursive helper for directory totals, processing subdirectories in parallel
+fn dir_totals_recursive<F>(path: &Path, count_lines_max: Option<u64>, include: &F, all: &mut SubtreeTotals) -> DirTotals
+where
+    F: Fn(&Path, &Metadata) -> bool + Sync,
+{
+    let mut totals = DirTotals::default();
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    
+    match std::fs::read_dir(path) {
+        Ok(entries) => {
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else { continue };
+                if !include(&entry.path(), &metadata) {
+                    continue;
+                }
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                } else {
+                    totals.size += metadata.len();
+                    totals.files += 1;
+                    files.push(entry.path());
+                }
+            }
+        }
+        Err(e) => log::debug!("Skipping totals for {:?}: {}", path, e),
+    }
+    
+    if let Some(max_size) = count_lines_max {
+        let paths: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+        let counts = count_lines_parallel(&paths, max_size);
+        totals.lines += counts.iter().sum::<u64>();
+        all.file_lines.extend(files.into_iter().zip(counts));
+    }
+    
+    let subtrees: Vec<(DirTotals, SubtreeTotals)> = dirs
+        .par_iter()
+        .map(|dir| {
+            let mut sub = SubtreeTotals::default();
+            let dir_totals = dir_totals_recursive(dir, count_lines_max, include, &mut sub);
+            (dir_totals, sub)
+        })
+        .collect();
+    
+    for (sub_totals, sub) in subtrees {
+        totals.add(&sub_totals);
+        all.dirs.extend(sub.dirs);
+        all.file_lines.extend(sub.file_lines);
+    }
+    
+    all.dirs.insert(path.to_path_buf(), totals);
+    totals
+}
+
+/// A path with its size, for largest-item reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizedPath {
+    /// Path of the file or directory
+    pub path: PathBuf,
+    /// Size in bytes (recursive for directories)
+    pub size: u64,
+}
+
+/// The largest paths seen so far, at most `limit` of them
+///
+/// Kept in a min-heap so the smallest kept item is dropped first; finding
+/// the N largest of M paths takes O(M log N) time and O(N) memory rather
+/// than sorting everything.
+#[derive(Debug, Clone, Default)]
+pub struct TopN {
+    limit: usize,
+    heap: BinaryHeap<Reverse<(u64, PathBuf)>>,
+}
+
+impl TopN {
+    /// Create an empty collection keeping up to `limit` items
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::with_capacity(limit),
+        }
+    }
+    
+    /// Offer a path, keeping it only if it is among the largest
+    pub fn push(&mut self, size: u64, path: PathBuf) {
+        if self.heap.len() < self.limit {
+            self.heap.push(Reverse((size, path)));
+        } else if self.heap.peek().is_some_and(|Reverse((smallest, _))| size > *smallest) {
+            self.heap.pop();
+            self.heap.push(Reverse((size, path)));
+        }
+    }
+    
+    /// Offer every item kept by another collection
+    pub fn merge(&mut self, other: TopN) {
+        for Reverse((size, path)) in other.heap {
+            self.push(size, path);
+        }
+    }
+    
+    /// Kept items, largest first
+    pub fn into_sorted(self) -> Vec<SizedPath> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, path))| SizedPath { path, size })
+            .collect()
+    }
+}
+
+/// Largest files and directories below a root
+#[derive(Debug, Clone, Default)]
+pub struct Largest {
+    /// Total size of all files considered
+    pub total_size: u64,
+    /// Largest files, largest first
+    pub files: Vec<SizedPath>,
+    /// Largest directories by recursive size, largest first
+    pub dirs: Vec<SizedPath>,
+}
+
+impl Largest {
+    /// Rank the files and directories of an already walked tree
+    ///
+    /// Directory sizes are added up from the files below them, so the tree
+    /// must hold every entry the filters accept. The roots are not ranked.
+    pub fn from_entries(entries: &[TreeEntry], files: usize, dirs: usize) -> Self {
+        fn add(entry: &TreeEntry, top_files: &mut TopN, top_dirs: &mut TopN) -> u64 {
+            if !entry.is_dir {
+                top_files.push(entry.size, entry.path.clone());
+                return entry.size;
+            }
+            
+            let size = entry.children.iter().map(|child| add(child, top_files, top_dirs)).sum();
+            top_dirs.push(size, entry.path.clone());
+            size
+        }
+        
+        let mut total_size = 0;
+        let mut top_files = TopN::new(files);
+        let mut top_dirs = TopN::new(dirs);
+        for entry in entries {
+            if entry.is_dir {
+                for child in &entry.children {
+                    total_size += add(child, &mut top_files, &mut top_dirs);
+                }
+            } else {
+                total_size += add(entry, &mut top_files, &mut top_dirs);
+            }
+        }
+        
+        Largest {
+            total_size,
+            files: top_files.into_sorted(),
+            dirs: top_dirs.into_sorted(),
+        }
+    }
+}
+
+/// Units used for durations: length in seconds, short suffix, long name
+const DURATION_UNITS: &[(u64, &str, &str)] = &[
+    (31_536_000, "y", "year"),
+    (2_592_000, "mo", "month"),
+    (604_800, "w", "week"),
+    (86_400, "d", "day"),
+    (3600, "h", "hour"),
+    (60, "m", "minute"),
+    (1, "s", "second"),
+];
+
+/// Index of the largest unit that fits in a duration
+fn leading_unit(secs: u64) -> usize {
+    DURATION_UNITS.iter()
+        .position(|&(unit, _, _)| secs >= unit)
+        .unwrap_or(DURATION_UNITS.len() - 1)
+}
+
 /// Format a duration in human-readable format
+///
+/// Shows the two largest units, e.g. `2m 5s`, `1h 2m` or `3d 4h`.
 pub fn format_duration(secs: u64) -> String {
-    if secs < 60 {
-        format!("{}s", secs)
-    } else if secs < 3600 {
-        format!("{}m {}s", secs / 60, secs % 60)
+    let i = leading_unit(secs);
+    let (unit, suffix, _) = DURATION_UNITS[i];
+    
+    match DURATION_UNITS.get(i + 1) {
+        Some(&(next, next_suffix, _)) => {
+            format!("{}{} {}{}", secs / unit, suffix, secs % unit / next, next_suffix)
+        }
+        None => format!("{}{}", secs, suffix),
+    }
+}
+
+/// Format how long ago something happened, e.g. `3 days ago`
+///
+/// Negative ages are in the future (`in 2 hours`).
+pub fn format_relative(age_secs: i64) -> String {
+    if age_secs == 0 {
+        return "just now".to_string();
+    }
+    
+    let secs = age_secs.unsigned_abs();
+    let (unit, _, name) = DURATION_UNITS[leading_unit(secs)];
+    let count = secs / unit;
+    let plural = if count == 1 { "" } else { "s" };
+    
+    if age_secs > 0 {
+        format!("{} {}{} ago", count, name, plural)
     } else {
-        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+        format!("in {} {}{}", count, name, plural)
+    }
+}
+
+/// Calendar date and time of day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    /// Offset from UTC in seconds
+    pub offset: i32,
+}
+
+impl DateTime {
+    /// Break a Unix timestamp into a UTC date and time
+    pub fn utc(secs: i64) -> Self {
+        let days = secs.div_euclid(86_400);
+        let rem = secs.rem_euclid(86_400) as u32;
+        
+        // Civil-from-days (Howard Hinnant's algorithm)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        
+        Self {
+            year,
+            month,
+            day,
+            hour: rem / 3600,
+            minute: rem % 3600 / 60,
+            second: rem % 60,
+            offset: 0,
+        }
+    }
+    
+    /// Local date and time of a timestamp
+    #[cfg(unix)]
+    pub fn local(time: SystemTime) -> Self {
+        let secs = unix_seconds(time);
+        let t = secs as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        
+        // SAFETY: both pointers refer to live values of the right type
+        if unsafe { libc::localtime_r(&t, &mut tm) }.is_null() {
+            return Self::utc(secs);
+        }
+        
+        Self {
+            year: i64::from(tm.tm_year) + 1900,
+            month: (tm.tm_mon + 1) as u32,
+            day: tm.tm_mday as u32,
+            hour: tm.tm_hour as u32,
+            minute: tm.tm_min as u32,
+            second: tm.tm_sec as u32,
+            offset: tm.tm_gmtoff as i32,
+        }
+    }
+    
+    /// Local date and time of a timestamp (UTC on this platform)
+    #[cfg(not(unix))]
+    pub fn local(time: SystemTime) -> Self {
+        Self::utc(unix_seconds(time))
+    }
+    
+    /// Days since 1970-01-01 (Howard Hinnant's days-from-civil)
+    fn days(&self) -> i64 {
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = i64::from((self.month + 9) % 12);
+        let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+    
+    /// Day of the week, 0 = Sunday
+    fn weekday(&self) -> usize {
+        (self.days() + 4).rem_euclid(7) as usize
+    }
+    
+    /// Day of the year, 1-based
+    fn ordinal(&self) -> i64 {
+        let jan1 = Self { month: 1, day: 1, ..*self };
+        self.days() - jan1.days() + 1
+    }
+    
+    /// Format with `strftime`-style conversions
+    ///
+    /// Supports `%Y %y %C %m %d %e %H %I %M %S %p %b %h %B %a %A %j %u %w
+    /// %s %z %:z %F %T %R %D %n %t %%`; anything else is copied as-is.
+    pub fn format(&self, pattern: &str) -> String {
+        const MONTHS: [&str; 12] = [
+            "January", "February", "March", "April", "May", "June",
+            "July", "August", "September", "October", "November", "December",
+        ];
+        const DAYS: [&str; 7] = [
+            "Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+        ];
+        
+        let month = MONTHS[(self.month as usize + 11) % 12];
+        let weekday = DAYS[self.weekday()];
+        let hour12 = match self.hour % 12 {
+            0 => 12,
+            hour => hour,
+        };
+        let offset = |colon: bool| {
+            let sign = if self.offset < 0 { '-' } else { '+' };
+            let minutes = self.offset.unsigned_abs() / 60;
+            let sep = if colon { ":" } else { "" };
+            format!("{}{:02}{}{:02}", sign, minutes / 60, sep, minutes % 60)
+        };
+        
+        let mut out = String::with_capacity(pattern.len() + 16);
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            
+            let spec = match chars.next() {
+                Some(':') if chars.as_str().starts_with('z') => {
+                    chars.next();
+                    out.push_str(&offset(true));
+                    continue;
+                }
+                Some(spec) => spec,
+                None => {
+                    out.push('%');
+                    break;
+                }
+            };
+            
+            let text = match spec {
+                'Y' => self.year.to_string(),
+                'y' => format!("{:02}", self.year.rem_euclid(100)),
+                'C' => format!("{:02}", self.year.div_euclid(100)),
+                'm' => format!("{:02}", self.month),
+                'd' => format!("{:02}", self.day),
+                'e' => format!("{:>2}", self.day),
+                'H' => format!("{:02}", self.hour),
+                'I' => format!("{:02}", hour12),
+                'M' => format!("{:02}", self.minute),
+                'S' => format!("{:02}", self.second),
+                'p' => if self.hour < 12 { "AM" } else { "PM" }.to_string(),
+                'b' | 'h' => month[..3].to_string(),
+                'B' => month.to_string(),
+                'a' => weekday[..3].to_string(),
+                'A' => weekday.to_string(),
+                'j' => format!("{:03}", self.ordinal()),
+                'u' => (if self.weekday() == 0 { 7 } else { self.weekday() }).to_string(),
+                'w' => self.weekday().to_string(),
+                's' => {
+                    let local = self.days() * 86_400
+                        + i64::from(self.hour * 3600 + self.minute * 60 + self.second);
+                    (local - i64::from(self.offset)).to_string()
+                }
+                'z' => offset(false),
+                'F' => self.format("%Y-%m-%d"),
+                'T' => self.format("%H:%M:%S"),
+                'R' => self.format("%H:%M"),
+                'D' => self.format("%m/%d/%y"),
+                'n' => "\n".to_string(),
+                't' => "\t".to_string(),
+                '%' => "%".to_string(),
+                other => format!("%{}", other),
+            };
+            out.push_str(&text);
+        }
+        
+        out
+    }
+}
+
+/// Seconds since the Unix epoch, negative for earlier times
+pub fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// How timestamps are displayed
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimeStyle {
+    /// ISO 8601 with the UTC offset, e.g. `2024-02-29T13:45:30+01:00`
+    Iso,
+    /// Date and minute, e.g. `2024-02-29 13:45`
+    #[default]
+    LongIso,
+    /// Age relative to now, e.g. `3 days ago`
+    Relative,
+    /// A `strftime`-style pattern
+    Custom(String),
+}
+
+impl TimeStyle {
+    /// Format a timestamp in local time
+    pub fn format(&self, time: SystemTime) -> String {
+        match self {
+            TimeStyle::Iso => DateTime::local(time).format("%Y-%m-%dT%H:%M:%S%:z"),
+            TimeStyle::LongIso => DateTime::local(time).format("%Y-%m-%d %H:%M"),
+            TimeStyle::Relative => {
+                format_relative(unix_seconds(SystemTime::now()) - unix_seconds(time))
+            }
+            TimeStyle::Custom(pattern) => DateTime::local(time).format(pattern),
+        }
+    }
+}
+
+impl FromStr for TimeStyle {
+    type Err = String;
+    
+    /// Parse a style name, or `+FORMAT` for a custom pattern like `ls`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(pattern) = s.strip_prefix('+') {
+            return Ok(TimeStyle::Custom(pattern.to_string()));
+        }
+        
+        match s.trim().to_lowercase().as_str() {
+            "iso" => Ok(TimeStyle::Iso),
+            "long-iso" => Ok(TimeStyle::LongIso),
+            "relative" => Ok(TimeStyle::Relative),
+            _ if s.contains('%') => Ok(TimeStyle::Custom(s.to_string())),
+            _ => Err(format!("unknown time style '{}' (use iso, long-iso, relative or +FORMAT)", s)),
+        }
+    }
+}
+
+impl fmt::Display for TimeStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeStyle::Iso => write!(f, "iso"),
+            TimeStyle::LongIso => write!(f, "long-iso"),
+            TimeStyle::Relative => write!(f, "relative"),
+            TimeStyle::Custom(pattern) => write!(f, "+{}", pattern),
+        }
+    }
+}
+
+impl TryFrom<String> for TimeStyle {
+    type Error = String;
+    
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TimeStyle> for String {
+    fn from(style: TimeStyle) -> Self {
+        style.to_string()
     }
 }
 
@@ -236,10 +751,111 @@ mod tests {
         assert!(is_binary_file(bin_file.path()).unwrap());
     }
     
+    #[test]
+    fn test_calculate_dir_totals() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/one.txt"), "1\n2\n").unwrap();
+        std::fs::write(root.join("a/b/two.txt"), "3\n").unwrap();
+        
+        std::fs::write(root.join("a/.hidden"), "secret\n").unwrap();
+        
+        let all = |_: &Path, _: &Metadata| true;
+        let totals = calculate_dir_totals(root, Some(1_000_000), &all);
+        assert_eq!(totals.dirs[root], DirTotals { size: 13, files: 3, lines: 4 });
+        
+        let visible = |path: &Path, _: &Metadata| {
+            !path.file_name().unwrap().to_string_lossy().starts_with('.')
+        };
+        let totals = calculate_dir_totals(root, Some(1_000_000), &visible);
+        assert_eq!(totals.dirs[root], DirTotals { size: 6, files: 2, lines: 3 });
+        assert_eq!(totals.dirs[&root.join("a/b")], DirTotals { size: 2, files: 1, lines: 1 });
+        assert_eq!(totals.file_lines[&root.join("a/one.txt")], 2);
+        
+        // Line counting is optional
+        let totals = calculate_dir_totals(root, None, &visible);
+        assert_eq!(totals.dirs[root].lines, 0);
+        assert!(totals.file_lines.is_empty());
+    }
+    
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(45), "45s");
         assert_eq!(format_duration(125), "2m 5s");
         assert_eq!(format_duration(3725), "1h 2m");
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(93_600), "1d 2h");
+        assert_eq!(format_duration(40 * 86_400), "1mo 1w");
+    }
+    
+    #[test]
+    fn test_format_relative() {
+        assert_eq!(format_relative(0), "just now");
+        assert_eq!(format_relative(1), "1 second ago");
+        assert_eq!(format_relative(150), "2 minutes ago");
+        assert_eq!(format_relative(3 * 86_400 + 5), "3 days ago");
+        assert_eq!(format_relative(400 * 86_400), "1 year ago");
+        assert_eq!(format_relative(-7200), "in 2 hours");
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_datetime_utc() {
+        let dt = DateTime::utc(0);
+        assert_eq!((dt.year, dt.month, dt.day, dt.hour), (1970, 1, 1, 0));
+        
+        // 2024-02-29 13:45:30 UTC, a leap day
+        let dt = DateTime::utc(1_709_214_330);
+        assert_eq!((dt.year, dt.month, dt.day), (2024, 2, 29));
+        assert_eq!((dt.hour, dt.minute, dt.second), (13, 45, 30));
+        
+        let dt = DateTime::utc(-86_400);
+        assert_eq!((dt.year, dt.month, dt.day), (1969, 12, 31));
+    }
+    
+    #[test]
+    fn test_datetime_format() {
+        let dt = DateTime::utc(1_709_214_330);
+        assert_eq!(dt.format("%F %T"), "2024-02-29 13:45:30");
+        assert_eq!(dt.format("%a %b %e %I:%M %p"), "Thu Feb 29 01:45 PM");
+        assert_eq!(dt.format("%A, %d %B %Y (day %j)"), "Thursday, 29 February 2024 (day 060)");
+        assert_eq!(dt.format("%s %z %:z %% %Q"), "1709214330 +0000 +00:00 % %Q");
+        
+        let dt = DateTime { offset: -5 * 3600 - 1800, ..DateTime::utc(1_709_214_330 - 5 * 3600 - 1800) };
+        assert_eq!(dt.format("%Y-%m-%dT%H:%M:%S%:z"), "2024-02-29T08:15:30-05:30");
+        assert_eq!(dt.format("%s"), "1709214330");
+    }
+    
+    #[test]
+    fn test_time_style_parsing() {
+        assert_eq!("iso".parse(), Ok(TimeStyle::Iso));
+        assert_eq!("Long-ISO".parse(), Ok(TimeStyle::LongIso));
+        assert_eq!("relative".parse(), Ok(TimeStyle::Relative));
+        assert_eq!("+%Y".parse(), Ok(TimeStyle::Custom("%Y".to_string())));
+        assert_eq!("%d/%m".parse(), Ok(TimeStyle::Custom("%d/%m".to_string())));
+        assert!("fancy".parse::<TimeStyle>().is_err());
+        
+        assert_eq!(TimeStyle::Custom("%H".to_string()).to_string(), "+%H");
+        assert_eq!(TimeStyle::Relative.format(SystemTime::now()), "just now");
+    }
+    
+    #[test]
+    fn test_top_n() {
+        let mut top = TopN::new(2);
+        top.push(5, PathBuf::from("five"));
+        top.push(1, PathBuf::from("one"));
+        top.push(9, PathBuf::from("nine"));
+        
+        let mut other = TopN::new(2);
+        other.push(7, PathBuf::from("seven"));
+        other.push(3, PathBuf::from("three"));
+        top.merge(other);
+        
+        let sizes: Vec<_> = top.into_sorted().into_iter().map(|item| item.size).collect();
+        assert_eq!(sizes, vec![9, 7]);
+        
+        let mut none = TopN::new(0);
+        none.push(1, PathBuf::from("one"));
+        assert!(none.into_sorted().is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+//! Size units and formatting
+//!
+//! Sizes can be shown in binary IEC units (`KiB`, base 1024), decimal SI
+//! units (`kB`, base 1000), raw bytes, or always in one fixed unit. Unit
+//! names are shared with size parsing, so `--min-size=1KiB` and
+//! `--units=KiB` mean the same thing.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// IEC unit labels, base 1024
+const IEC_UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// SI unit labels, base 1000
+const SI_UNITS: &[&str] = &["B", "kB", "MB", "GB", "TB", "PB"];
+
+/// Unit system used to display sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SizeUnits {
+    /// Binary units scaled to fit (`1.5 KiB`)
+    #[default]
+    Iec,
+    /// Decimal units scaled to fit (`1.5 kB`)
+    Si,
+    /// Plain byte counts
+    Bytes,
+    /// Always the same unit, given by its base and power (`MiB` = 1024²)
+    Fixed { base: u64, power: u32 },
+}
+
+impl SizeUnits {
+    /// Base between successive units
+    pub fn base(self) -> u64 {
+        match self {
+            SizeUnits::Si => 1000,
+            SizeUnits::Fixed { base, .. } => base,
+            SizeUnits::Iec | SizeUnits::Bytes => 1024,
+        }
+    }
+    
+    /// Unit labels for this system
+    fn labels(self) -> &'static [&'static str] {
+        if self.base() == 1000 { SI_UNITS } else { IEC_UNITS }
+    }
+}
+
+impl FromStr for SizeUnits {
+    type Err = String;
+    
+    /// Parse `iec`, `si`, `bytes` or a fixed unit such as `MiB` or `kB`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "iec" | "binary" => return Ok(SizeUnits::Iec),
+            "si" | "decimal" => return Ok(SizeUnits::Si),
+            "bytes" => return Ok(SizeUnits::Bytes),
+            _ => {}
+        }
+        
+        parse_unit(s.trim())
+            .map(|(base, power)| SizeUnits::Fixed { base, power })
+            .ok_or_else(|| format!("unknown size units '{}' (use iec, si, bytes or a unit like MiB or kB)", s))
+    }
+}
+
+impl fmt::Display for SizeUnits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeUnits::Iec => write!(f, "iec"),
+            SizeUnits::Si => write!(f, "si"),
+            SizeUnits::Bytes => write!(f, "bytes"),
+            SizeUnits::Fixed { power, .. } => write!(f, "{}", self.labels()[*power as usize]),
+        }
+    }
+}
+
+impl TryFrom<String> for SizeUnits {
+    type Error = String;
+    
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SizeUnits> for String {
+    fn from(units: SizeUnits) -> Self {
+        units.to_string()
+    }
+}
+
+/// How sizes are written: unit system and decimal places
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeFormat {
+    /// Unit system
+    pub units: SizeUnits,
+    /// Decimal places for scaled sizes
+    pub precision: usize,
+}
+
+impl SizeFormat {
+    /// Format a size, e.g. `1.5 KiB`
+    ///
+    /// Sizes below one unit are always whole bytes.
+    pub fn format(&self, size: u64) -> String {
+        let labels = self.units.labels();
+        let base = self.units.base() as f64;
+        
+        let (value, power) = match self.units {
+            SizeUnits::Bytes => return format!("{} B", size),
+            SizeUnits::Fixed { power, .. } => (size as f64 / base.powi(power as i32), power as usize),
+            SizeUnits::Iec | SizeUnits::Si => {
+                let mut value = size as f64;
+                let mut power = 0;
+                while value >= base && power < labels.len() - 1 {
+                    value /= base;
+                    power += 1;
+                }
+                (value, power)
+            }
+        };
+        
+        if power == 0 {
+            format!("{} B", size)
+        } else {
+            format!("{:.*} {}", self.precision, value, labels[power])
+        }
+    }
+    
+    /// Label for a whole number of units, e.g. `10 MiB`, for bucket names
+    pub fn label(&self, count: u64, power: u32) -> String {
+        format!("{} {}", count, self.units.labels()[power as usize])
+    }
+}
+
+impl Default for SizeFormat {
+    fn default() -> Self {
+        Self {
+            units: SizeUnits::Iec,
+            precision: 1,
+        }
+    }
+}
+
+/// Look up a unit name as (base, power)
+///
+/// Follows GNU conventions: `K` and `KiB` are 1024 bytes, `kB` and `KB`
+/// are 1000. Letters are case-insensitive apart from that distinction.
+pub fn parse_unit(unit: &str) -> Option<(u64, u32)> {
+    let lower = unit.to_lowercase();
+    if lower.is_empty() || lower == "b" {
+        return Some((1024, 0));
+    }
+    
+    let mut chars = lower.chars();
+    let prefix = chars.next()?;
+    let suffix = chars.as_str();
+    let power = match prefix {
+        'k' => 1,
+        'm' => 2,
+        'g' => 3,
+        't' => 4,
+        'p' => 5,
+        _ => return None,
+    };
+    
+    match suffix {
+        "" | "ib" => Some((1024, power)),
+        "b" => Some((1000, power)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_unit() {
+        assert_eq!(parse_unit("B"), Some((1024, 0)));
+        assert_eq!(parse_unit("K"), Some((1024, 1)));
+        assert_eq!(parse_unit("KiB"), Some((1024, 1)));
+        assert_eq!(parse_unit("kB"), Some((1000, 1)));
+        assert_eq!(parse_unit("MB"), Some((1000, 2)));
+        assert_eq!(parse_unit("gib"), Some((1024, 3)));
+        assert_eq!(parse_unit("XB"), None);
+        assert_eq!(parse_unit("KiBs"), None);
+        assert_eq!(parse_unit("ü"), None);
+        assert_eq!(parse_unit("µB"), None);
+    }
+    
+    #[test]
+    fn test_size_units_parsing() {
+        assert_eq!("iec".parse(), Ok(SizeUnits::Iec));
+        assert_eq!("SI".parse(), Ok(SizeUnits::Si));
+        assert_eq!("bytes".parse(), Ok(SizeUnits::Bytes));
+        assert_eq!("MiB".parse(), Ok(SizeUnits::Fixed { base: 1024, power: 2 }));
+        assert_eq!("kB".parse(), Ok(SizeUnits::Fixed { base: 1000, power: 1 }));
+        assert!("furlongs".parse::<SizeUnits>().is_err());
+        
+        assert_eq!(SizeUnits::Fixed { base: 1000, power: 2 }.to_string(), "MB");
+        assert_eq!(SizeUnits::Fixed { base: 1024, power: 1 }.to_string(), "KiB");
+    }
+    
+    #[test]
+    fn test_size_format() {
+        let iec = SizeFormat::default();
+        assert_eq!(iec.format(1023), "1023 B");
+        assert_eq!(iec.format(1536), "1.5 KiB");
+        
+        let si = SizeFormat { units: SizeUnits::Si, precision: 2 };
+        assert_eq!(si.format(999), "999 B");
+        assert_eq!(si.format(1536), "1.54 kB");
+        assert_eq!(si.format(2_500_000), "2.50 MB");
+        
+        let bytes = SizeFormat { units: SizeUnits::Bytes, precision: 1 };
+        assert_eq!(bytes.format(1_048_576), "1048576 B");
+        
+        let fixed = SizeFormat { units: "MiB".parse().unwrap(), precision: 3 };
+        assert_eq!(fixed.format(524_288), "0.500 MiB");
+        assert_eq!(fixed.format(3 * 1_048_576), "3.000 MiB");
+        
+        let whole = SizeFormat { units: SizeUnits::Iec, precision: 0 };
+        assert_eq!(whole.format(1536), "2 KiB");
+        assert_eq!(si.label(10, 2), "10 MB");
+    }
+}
@@ -0,0 +1,97 @@
+//! User and group name lookup
+//!
+//! Resolves numeric user and group IDs to names. Lookups go through the
+//! system databases once per ID and are cached for the rest of the run.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Caches of resolved user and group names
+static USER_NAMES: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+static GROUP_NAMES: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+
+/// Name of a user, or the numeric ID if it has no entry
+pub fn user_name(uid: u32) -> String {
+    cached_name(&USER_NAMES, uid, lookup_user)
+}
+
+/// Name of a group, or the numeric ID if it has no entry
+pub fn group_name(gid: u32) -> String {
+    cached_name(&GROUP_NAMES, gid, lookup_group)
+}
+
+/// Look up a name through a cache
+fn cached_name(
+    cache: &OnceLock<Mutex<HashMap<u32, String>>>,
+    id: u32,
+    lookup: fn(u32) -> Option<String>,
+) -> String {
+    let mut cache = cache.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    cache.entry(id)
+        .or_insert_with(|| lookup(id).unwrap_or_else(|| id.to_string()))
+        .clone()
+}
+
+/// Look up a user name in the system user database
+#[cfg(unix)]
+fn lookup_user(uid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    
+    // SAFETY: all pointers refer to live, correctly sized buffers
+    let ret = unsafe {
+        libc::getpwuid_r(uid, &mut passwd, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+    
+    // SAFETY: on success pw_name points to a NUL-terminated string in `buf`
+    let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Look up a group name in the system group database
+#[cfg(unix)]
+fn lookup_group(gid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    
+    // SAFETY: all pointers refer to live, correctly sized buffers
+    let ret = unsafe {
+        libc::getgrgid_r(gid, &mut group, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+    
+    // SAFETY: on success gr_name points to a NUL-terminated string in `buf`
+    let name = unsafe { std::ffi::CStr::from_ptr(group.gr_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_gid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_name_lookup() {
+        assert_eq!(user_name(0), "root");
+        // IDs without an entry fall back to the number
+        assert_eq!(user_name(4_000_000_000), "4000000000");
+        assert_eq!(group_name(4_000_000_000), "4000000000");
+    }
+}
@@ -12,16 +12,25 @@
 //! - **Feature detection**: Automatically falls back to slower paths when advanced
 //!   features are requested that require more processing
 
-use crate::{FilterOptions, Result, Error};
-use crate::formatter::OutputFormat as FormatterOutputFormat;
-use crate::filters::compare_entries;
-use crate::stats::{calculate_dir_size, count_lines};
+use crate::{FilterOptions, Result, Error, SortBy};
+use crate::colors::{paint_name, Indicator, LsColors};
+use crate::icons::Icons;
+use crate::units::SizeFormat;
+use crate::users::{group_name, user_name};
+use crate::formatter::{format_collapsed, format_unopened, match_summary, OutputFormat as FormatterOutputFormat, PathDisplay, TreeChars};
+use crate::filters::{compare_by_keys, Grouping};
+use crate::classify::apply_classification;
+use crate::languages::{apply_code_stats, count_code, CodeStats};
+use crate::search::{apply_content_search, apply_fuzzy_search, name_match_ranges, ContentMatches, FuzzyMatch};
+use crate::stats::{calculate_dir_size, calculate_dir_totals, count_lines, count_lines_parallel, DirTotals, Largest, TopN};
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, Metadata};
+use std::collections::HashMap;
 use std::io::{self, Write, BufWriter};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::SystemTime;
 use colored::*;
 
@@ -52,6 +61,15 @@ pub struct TreeEntry {
     pub size: u64,
     /// Number of lines (0 for directories and binary files)
     pub line_count: u64,
+    /// Number of files below a directory (only computed when needed)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub file_count: u64,
+    /// Directory shown as a summary, with totals but no children
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub collapsed: bool,
+    /// Entry count of a directory left unopened by the file limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unopened_entries: Option<usize>,
     /// Modification time
     pub modified: SystemTime,
     /// Is this a directory?
@@ -60,10 +78,97 @@ pub struct TreeEntry {
     pub is_symlink: bool,
     /// Is this executable?
     pub is_executable: bool,
+    /// File type used for `LS_COLORS`, read once while walking
+    #[serde(skip)]
+    pub indicator: Indicator,
     /// Child entries
     pub children: Vec<TreeEntry>,
     /// Depth from root
     pub depth: usize,
+    /// Content search matches (only set when searching file contents)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<ContentMatches>,
+    /// Fuzzy search score and matched positions (only set when fuzzy searching)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuzzy: Option<FuzzyMatch>,
+    /// Language and code/comment/blank line counts (only set with code stats)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_stats: Option<CodeStats>,
+    /// File category such as "Images" or "Code" (only set when classifying)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// MIME type (only set when classifying)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime: Option<String>,
+    /// Permissions, ownership and inode details (only set with extended metadata)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<FileMetadata>,
+}
+
+/// Permissions, ownership, inode and timestamp details of an entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileMetadata {
+    /// File type and permission bits
+    pub mode: u32,
+    /// Owner user ID
+    pub uid: u32,
+    /// Owner user name (the ID when it has no name)
+    pub user: String,
+    /// Owner group ID
+    pub gid: u32,
+    /// Owner group name (the ID when it has no name)
+    pub group: String,
+    /// Inode number
+    pub inode: u64,
+    /// ID of the device containing the entry
+    pub device: u64,
+    /// Number of hard links
+    pub nlink: u64,
+    /// Last access time
+    pub accessed: SystemTime,
+    /// Last status change time
+    pub changed: SystemTime,
+    /// Creation time, where the filesystem records it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<SystemTime>,
+}
+
+impl FileMetadata {
+    /// Capture details from `symlink_metadata`
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        use std::time::Duration;
+        
+        let timestamp = |secs: i64, nanos: i64| {
+            let offset = Duration::new(secs.unsigned_abs(), nanos as u32);
+            if secs >= 0 {
+                SystemTime::UNIX_EPOCH + offset
+            } else {
+                SystemTime::UNIX_EPOCH - offset
+            }
+        };
+        
+        Some(Self {
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            user: user_name(metadata.uid()),
+            gid: metadata.gid(),
+            group: group_name(metadata.gid()),
+            inode: metadata.ino(),
+            device: metadata.dev(),
+            nlink: metadata.nlink(),
+            accessed: timestamp(metadata.atime(), metadata.atime_nsec()),
+            changed: timestamp(metadata.ctime(), metadata.ctime_nsec()),
+            created: metadata.created().ok(),
+        })
+    }
+    
+    /// Capture details from metadata (not available on this platform)
+    #[cfg(not(unix))]
+    pub fn from_metadata(_metadata: &Metadata) -> Option<Self> {
+        None
+    }
 }
 
 /// Walker mode based on features requested
@@ -77,6 +182,24 @@ enum WalkerMode {
     Full,
 }
 
+/// Children of a directory as read by the walker
+enum Listing {
+    /// Filtered, sorted and limited child paths
+    Children(Vec<PathBuf>),
+    /// Too many entries to open, with their count
+    OverLimit(usize),
+}
+
+impl Listing {
+    /// Child paths, or none when the directory was not opened
+    fn into_children(self) -> Vec<PathBuf> {
+        match self {
+            Listing::Children(children) => children,
+            Listing::OverLimit(_) => Vec::new(),
+        }
+    }
+}
+
 /// Unified walker that automatically chooses optimal implementation
 pub struct Walker {
     root: PathBuf,
@@ -86,7 +209,16 @@ pub struct Walker {
     max_file_size: u64,
     show_lines: bool,
     dir_sizes: bool,
+    code_stats: bool,
+    classify: bool,
+    extended_metadata: bool,
     mode: WalkerMode,
+    /// Recursive directory totals cached for sorting
+    dir_totals: Mutex<HashMap<PathBuf, DirTotals>>,
+    /// File line counts cached so each file is only counted once
+    line_counts: Mutex<HashMap<PathBuf, u64>>,
+    /// Pruning answers for directories looked ahead into but not listed yet
+    survivors: Mutex<HashMap<PathBuf, bool>>,
 }
 
 impl Walker {
@@ -118,7 +250,13 @@ impl Walker {
             max_file_size: 1_073_741_824, // 1GB default
             show_lines: false,
             dir_sizes: false,
+            code_stats: false,
+            classify: false,
+            extended_metadata: false,
             mode,
+            dir_totals: Mutex::new(HashMap::new()),
+            line_counts: Mutex::new(HashMap::new()),
+            survivors: Mutex::new(HashMap::new()),
         })
     }
     
@@ -143,10 +281,34 @@ impl Walker {
         self.mode = WalkerMode::Full;
     }
     
+    /// Enable per-language code statistics
+    pub fn enable_code_stats(&mut self) {
+        self.code_stats = true;
+        // Code stats are computed in a post-processing pass
+        self.mode = WalkerMode::Full;
+    }
+    
+    /// Capture permissions, ownership, inode and timestamps for each entry
+    pub fn enable_extended_metadata(&mut self) {
+        self.extended_metadata = true;
+        if matches!(self.mode, WalkerMode::FastPath) {
+            self.mode = WalkerMode::Standard;
+        }
+    }
+    
+    /// Enable file classification (kind and MIME type)
+    pub fn enable_classification(&mut self) {
+        self.classify = true;
+        // Classification is computed in a post-processing pass
+        self.mode = WalkerMode::Full;
+    }
+    
     /// Determine the optimal walker mode based on requested features
     fn determine_mode(filter_opts: &FilterOptions, gitignore: &Option<Gitignore>) -> WalkerMode {
         // Check if we need full mode (complex features)
         if filter_opts.search.is_some() ||
+           filter_opts.contains.is_some() ||
+           filter_opts.fuzzy.is_some() ||
            filter_opts.min_size.is_some() ||
            filter_opts.max_size.is_some() ||
            filter_opts.newer_than.is_some() ||
@@ -160,9 +322,13 @@ impl Walker {
            filter_opts.exclude.is_some() ||
            filter_opts.only_dirs ||
            filter_opts.only_files ||
-           filter_opts.sort_by.is_some() ||
+           !filter_opts.sort_keys.is_empty() ||
+           filter_opts.grouping != Grouping::Mixed ||
            filter_opts.max_dirs.is_some() ||
-           filter_opts.max_files.is_some() {
+           filter_opts.max_files.is_some() ||
+           filter_opts.collapse.is_some() ||
+           filter_opts.file_limit.is_some() ||
+           filter_opts.prunes_directories() {
             return WalkerMode::Standard;
         }
         
@@ -299,15 +465,35 @@ impl Walker {
             }
         }
         
-        // Read and process children
-        let children_paths = self.read_directory(&parent.path, parent.depth + 1)?;
+        // Read and process children, leaving directories over the file limit unopened
+        let children_paths = match self.read_directory(&parent.path, parent.depth + 1)? {
+            Listing::Children(children) => children,
+            Listing::OverLimit(count) => {
+                parent.unopened_entries = Some(count);
+                return Ok(());
+            }
+        };
         
         for child_path in children_paths {
             match self.process_entry(&child_path, parent.depth + 1) {
                 Ok(Some(mut child_entry)) => {
                     // Recursively process if it's a directory
                     if child_entry.is_dir {
-                        self.process_directory_children(&mut child_entry)?;
+                        // Collapsed directories get totals instead of children
+                        let has_children = if let Some(totals) = self.collapsed_totals(&child_entry.path) {
+                            child_entry.size = totals.size;
+                            child_entry.file_count = totals.files;
+                            child_entry.collapsed = true;
+                            totals.files > 0
+                        } else {
+                            self.process_directory_children(&mut child_entry)?;
+                            !child_entry.children.is_empty() || child_entry.unopened_entries.is_some()
+                        };
+                        
+                        // Children are filtered now, so pruning can be decided
+                        if !self.keep_directory(&child_entry.path, child_entry.depth, has_children) {
+                            continue;
+                        }
                     }
                     parent.children.push(child_entry);
                 }
@@ -329,6 +515,26 @@ impl Walker {
         // Start with standard walk
         let mut entries = self.walk_standard()?;
         
+        // Post-process: search file contents, dropping files without matches
+        if let Some(regex) = &self.filter_opts.contains {
+            apply_content_search(&mut entries, regex, self.max_file_size);
+        }
+        
+        // Post-process: rank files by fuzzy score and keep the best ones
+        if let Some(query) = &self.filter_opts.fuzzy {
+            apply_fuzzy_search(&mut entries, query, &self.root, self.filter_opts.fuzzy_limit);
+        }
+        
+        // Post-process: detect languages and count code lines in parallel
+        if self.code_stats {
+            apply_code_stats(&mut entries, self.max_file_size);
+        }
+        
+        // Post-process: classify files by extension and magic bytes
+        if self.classify {
+            apply_classification(&mut entries, &self.filter_opts.classifier);
+        }
+        
         // Post-process: calculate directory sizes if requested
         if self.dir_sizes {
             self.calculate_dir_sizes(&mut entries)?;
@@ -369,7 +575,7 @@ impl Walker {
         };
         
         let line_count = if self.show_lines && metadata.is_file() && size <= self.max_file_size {
-            count_lines(path, self.max_file_size).unwrap_or(0)
+            self.line_count(path)
         } else {
             0
         };
@@ -377,30 +583,61 @@ impl Walker {
         let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
         let is_symlink = metadata.is_symlink();
         let is_executable = is_executable(metadata);
+        let extended = if self.extended_metadata {
+            FileMetadata::from_metadata(metadata)
+        } else {
+            None
+        };
         
         Ok(TreeEntry {
             name,
             path: path.to_path_buf(),
             size,
             line_count,
+            file_count: 0,
+            collapsed: false,
+            unopened_entries: None,
             modified,
             is_dir: metadata.is_dir(),
             is_symlink,
             is_executable,
+            indicator: Indicator::of(path, Some(metadata)),
             children: Vec::new(),
             depth,
+            matches: None,
+            fuzzy: None,
+            code_stats: None,
+            kind: None,
+            mime: None,
+            metadata: extended,
         })
     }
     
     /// Read directory and return filtered, sorted, limited children
-    fn read_directory(&self, path: &Path, depth: usize) -> Result<Vec<PathBuf>> {
+    ///
+    /// Directories with more entries than the file limit are not opened;
+    /// entries are counted by name, before any metadata is read.
+    fn read_directory(&self, path: &Path, depth: usize) -> Result<Listing> {
         let mut entries = Vec::new();
         let mut dirs = Vec::new();
         let mut files = Vec::new();
         
-        // Read directory entries
+        // Read directory entries, skipping hidden ones by name
+        let mut dir_entries = Vec::new();
         for entry in fs::read_dir(path)? {
             let entry = entry?;
+            if self.filter_opts.show_hidden || !entry.file_name().to_string_lossy().starts_with('.') {
+                dir_entries.push(entry);
+            }
+        }
+        
+        if let Some(limit) = self.filter_opts.file_limit {
+            if dir_entries.len() > limit {
+                return Ok(Listing::OverLimit(dir_entries.len()));
+            }
+        }
+        
+        for entry in dir_entries {
             let path = entry.path();
             let metadata = entry.metadata()?;
             
@@ -418,32 +655,74 @@ impl Walker {
         }
         
         // Sort if requested
-        if let Some(sort_by) = self.filter_opts.sort_by {
+        if !self.filter_opts.sort_keys.is_empty() {
+            let needs_totals = self.filter_opts.sort_keys.iter().any(|key| key.field.uses_dir_totals());
+            
+            // Count lines of all files up front, in parallel, when sorting by them
+            if self.sorts_by_lines() {
+                let paths: Vec<&Path> = files.iter().map(|(path, _)| path.as_path()).collect();
+                self.count_lines_cached(&paths);
+            }
+            
             // Create temporary entries for sorting
             let mut sort_entries: Vec<TreeEntry> = dirs.iter()
                 .chain(files.iter())
                 .map(|(path, metadata)| {
-                    TreeEntry {
+                    let mut entry = TreeEntry {
                         name: path.file_name().unwrap().to_string_lossy().to_string(),
                         path: path.clone(),
                         size: metadata.len(),
                         line_count: 0,
+                        file_count: 0,
+                        collapsed: false,
+                        unopened_entries: None,
                         modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
                         is_dir: metadata.is_dir(),
                         is_symlink: metadata.is_symlink(),
                         is_executable: is_executable(metadata),
+                        indicator: Indicator::of(path, Some(metadata)),
                         children: Vec::new(),
                         depth,
+                        matches: None,
+                        fuzzy: None,
+                        code_stats: None,
+                        kind: None,
+                        mime: None,
+                        metadata: None,
+                    };
+                    
+                    // Directories sort by what they contain, not their inode size
+                    if needs_totals && entry.is_dir {
+                        let totals = self.dir_totals(path);
+                        entry.size = totals.size;
+                        entry.line_count = totals.lines;
+                        entry.file_count = totals.files;
+                    } else if self.sorts_by_lines() && !entry.is_dir {
+                        entry.line_count = self.line_count(path);
                     }
+                    
+                    entry
                 })
                 .collect();
             
+            let grouping = self.filter_opts.grouping;
             sort_entries.sort_by(|a, b| {
-                compare_entries(a, b, sort_by, self.filter_opts.reverse_sort)
+                grouping.compare(a.is_dir, b.is_dir).then_with(|| {
+                    compare_by_keys(
+                        a,
+                        b,
+                        &self.filter_opts.sort_keys,
+                        self.filter_opts.reverse_sort,
+                        self.filter_opts.ignore_case_sort,
+                    )
+                })
             });
             
             // Extract sorted paths
             entries = sort_entries.into_iter().map(|e| e.path).collect();
+        } else if self.filter_opts.grouping == Grouping::FilesFirst {
+            entries.extend(files.into_iter().map(|(p, _)| p));
+            entries.extend(dirs.into_iter().map(|(p, _)| p));
         } else {
             // No sorting, just combine
             entries.extend(dirs.into_iter().map(|(p, _)| p));
@@ -477,20 +756,159 @@ impl Walker {
             limited.push(path);
         }
         
-        Ok(limited)
+        Ok(Listing::Children(limited))
     }
     
-    /// Check if entry should be included based on filters
-    fn should_include(&self, path: &Path, metadata: &Metadata) -> bool {
-        // Check gitignore
-        if let Some(gitignore) = &self.gitignore {
-            if gitignore.matched(path, metadata.is_dir()).is_ignore() {
-                return false;
+    /// Read directory children, dropping subdirectories that would be pruned
+    ///
+    /// Used by the streaming walker, which has to know which children survive
+    /// before printing any of them so that the last-entry connectors are right.
+    fn visible_children(&self, path: &Path, depth: usize) -> Result<Listing> {
+        let children = match self.read_directory(path, depth)? {
+            Listing::Children(children) if self.filter_opts.prunes_directories() => children,
+            listing => return Ok(listing),
+        };
+        
+        let mut visible = Vec::with_capacity(children.len());
+        for child in children {
+            if !child.is_dir() || self.directory_survives(&child, depth)? {
+                visible.push(child);
+            }
+        }
+        
+        Ok(Listing::Children(visible))
+    }
+    
+    /// Look ahead into a directory to see if anything in it survives filtering
+    ///
+    /// The answers for subdirectories are kept until the streaming walker
+    /// lists them, so each directory is only looked into once.
+    fn directory_survives(&self, path: &Path, depth: usize) -> Result<bool> {
+        if let Some(survives) = self.survivors.lock().unwrap().remove(path) {
+            return Ok(survives);
+        }
+        
+        if let Some(totals) = self.collapsed_totals(path) {
+            return Ok(self.keep_directory(path, depth, totals.files > 0));
+        }
+        
+        if !self.at_depth_limit(depth) {
+            let children = match self.read_directory(path, depth + 1)? {
+                Listing::Children(children) => children,
+                // Unopened directories are always shown
+                Listing::OverLimit(_) => return Ok(true),
+            };
+            for child in children {
+                // Any surviving file keeps the whole chain of ancestors
+                if !child.is_dir() {
+                    return Ok(true);
+                }
+                let survives = self.directory_survives(&child, depth + 1)?;
+                self.survivors.lock().unwrap().insert(child, survives);
+                if survives {
+                    return Ok(true);
+                }
             }
         }
         
-        // Apply other filters
-        self.filter_opts.should_include(path, metadata)
+        Ok(self.keep_directory(path, depth, false))
+    }
+    
+    /// Decide whether a directory is kept once its filtered children are known
+    fn keep_directory(&self, path: &Path, depth: usize, has_children: bool) -> bool {
+        if !self.filter_opts.prunes_directories() || has_children {
+            return true;
+        }
+        
+        if self.filter_opts.empty_only {
+            // Only directories with no entries at all count as empty
+            return is_empty_dir(path);
+        }
+        
+        // Contents beyond the depth limit were never examined, so keep them
+        self.at_depth_limit(depth)
+    }
+    
+    /// Check whether children of an entry at this depth are beyond the depth limit
+    fn at_depth_limit(&self, depth: usize) -> bool {
+        self.filter_opts.max_depth.is_some_and(|max_depth| depth >= max_depth)
+    }
+    
+    /// Recursive totals for a directory, computed once per subtree and cached
+    fn dir_totals(&self, path: &Path) -> DirTotals {
+        if let Some(totals) = self.dir_totals.lock().unwrap().get(path) {
+            return *totals;
+        }
+        
+        // Lines are only worth counting when sorting by them
+        let count_lines = self.sorts_by_lines().then_some(self.max_file_size);
+        // Only count what the tree would show, so hidden and ignored files
+        // do not sway the ranking
+        let include = |path: &Path, metadata: &Metadata| {
+            !self.is_ignored(path, metadata) && self.filter_opts.matches_filters(path, metadata)
+        };
+        let computed = calculate_dir_totals(path, count_lines, &include);
+        let totals = computed.dirs.get(path).copied().unwrap_or_default();
+        self.dir_totals.lock().unwrap().extend(computed.dirs);
+        self.line_counts.lock().unwrap().extend(computed.file_lines);
+        
+        totals
+    }
+    
+    /// Totals for a directory matched by `--collapse`, which is not expanded
+    fn collapsed_totals(&self, path: &Path) -> Option<DirTotals> {
+        self.filter_opts.collapses(path).then(|| self.dir_totals(path))
+    }
+    
+    /// Check whether any sort key compares line counts
+    fn sorts_by_lines(&self) -> bool {
+        self.filter_opts.sort_keys.iter().any(|key| key.field == SortBy::Lines)
+    }
+    
+    /// Count lines for files that are not cached yet, in parallel
+    fn count_lines_cached(&self, paths: &[&Path]) {
+        let missing: Vec<&Path> = {
+            let cache = self.line_counts.lock().unwrap();
+            paths.iter().copied().filter(|path| !cache.contains_key(*path)).collect()
+        };
+        if missing.is_empty() {
+            return;
+        }
+        
+        let counts = count_lines_parallel(&missing, self.max_file_size);
+        let mut cache = self.line_counts.lock().unwrap();
+        for (path, count) in missing.into_iter().zip(counts) {
+            cache.insert(path.to_path_buf(), count);
+        }
+    }
+    
+    /// Line count of a file, served from the cache when it was already counted
+    ///
+    /// Only sorting by lines asks for a count twice, so otherwise the file is
+    /// counted directly and the cache doesn't grow with the tree.
+    fn line_count(&self, path: &Path) -> u64 {
+        if !self.sorts_by_lines() {
+            return count_lines(path, self.max_file_size).unwrap_or(0);
+        }
+        
+        if let Some(count) = self.line_counts.lock().unwrap().get(path) {
+            return *count;
+        }
+        
+        let count = count_lines(path, self.max_file_size).unwrap_or(0);
+        self.line_counts.lock().unwrap().insert(path.to_path_buf(), count);
+        count
+    }
+    
+    /// Check if entry should be included based on filters
+    fn should_include(&self, path: &Path, metadata: &Metadata) -> bool {
+        !self.is_ignored(path, metadata) && self.filter_opts.should_include(path, metadata)
+    }
+    
+    /// Check whether a path is matched by the gitignore rules
+    fn is_ignored(&self, path: &Path, metadata: &Metadata) -> bool {
+        self.gitignore.as_ref()
+            .is_some_and(|gitignore| gitignore.matched(path, metadata.is_dir()).is_ignore())
     }
     
     /// Calculate directory sizes recursively
@@ -515,6 +933,68 @@ impl Walker {
         Ok(())
     }
     
+    /// Find the largest files and directories without building a tree
+    ///
+    /// Filters apply, but display limits such as the depth, `--max-files`
+    /// or `--collapse` don't. Directory sizes are recursive; the root itself
+    /// is not ranked.
+    pub fn largest(&self, files: usize, dirs: usize) -> Largest {
+        let (total_size, top_files, top_dirs) = self.scan_largest(&self.root, files, dirs);
+        Largest {
+            total_size,
+            files: top_files.into_sorted(),
+            dirs: top_dirs.into_sorted(),
+        }
+    }
+    
+    /// Recursive helper for `largest`, scanning subdirectories in parallel
+    ///
+    /// Returns the directory's total size with its largest files and
+    /// subdirectories.
+    fn scan_largest(&self, path: &Path, files: usize, dirs: usize) -> (u64, TopN, TopN) {
+        let mut size = 0;
+        let mut top_files = TopN::new(files);
+        let mut top_dirs = TopN::new(dirs);
+        let mut subdirs = Vec::new();
+        
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    let child = entry.path();
+                    let Ok(metadata) = entry.metadata() else { continue };
+                    if !self.should_include(&child, &metadata) {
+                        continue;
+                    }
+                    
+                    if metadata.is_dir() {
+                        subdirs.push(child);
+                    } else {
+                        size += metadata.len();
+                        top_files.push(metadata.len(), child);
+                    }
+                }
+            }
+            Err(e) => log::debug!("Skipping {:?} for largest items: {}", path, e),
+        }
+        
+        let subtrees: Vec<_> = subdirs
+            .into_par_iter()
+            .map(|dir| {
+                let scanned = self.scan_largest(&dir, files, dirs);
+                (dir, scanned)
+            })
+            .collect();
+        
+        for (dir, (dir_size, dir_files, dir_dirs)) in subtrees {
+            size += dir_size;
+            top_files.merge(dir_files);
+            top_dirs.merge(dir_dirs);
+            top_dirs.push(dir_size, dir);
+        }
+        
+        (size, top_files, top_dirs)
+    }
+    
     /// Recursively calculate directory sizes
     fn calculate_dir_sizes_recursive(&self, entries: &mut [TreeEntry]) -> Result<()> {
         for entry in entries {
@@ -534,9 +1014,14 @@ pub struct StreamWalker<'a> {
     format: FormatterOutputFormat,
     show_size: bool,
     show_lines: bool,
-    unicode: bool,
+    code_stats: bool,
+    tree_chars: TreeChars,
     max_file_size: u64,
     color_enabled: bool,
+    colors: LsColors,
+    icons: Option<Icons>,
+    size_format: SizeFormat,
+    paths: PathDisplay,
     file_count: usize,
     dir_count: usize,
 }
@@ -549,7 +1034,7 @@ impl<'a> StreamWalker<'a> {
         format: FormatterOutputFormat,
         show_size: bool,
         show_lines: bool,
-        unicode: bool,
+        tree_chars: TreeChars,
     ) -> Self {
         let stdout: Box<dyn Write> = Box::new(io::stdout());
         let color_enabled = atty::is(atty::Stream::Stdout);
@@ -559,14 +1044,57 @@ impl<'a> StreamWalker<'a> {
             format,
             show_size,
             show_lines,
-            unicode,
+            code_stats: false,
+            tree_chars,
             max_file_size: 1_073_741_824, // 1GB default
             color_enabled,
+            colors: LsColors::default(),
+            icons: None,
+            size_format: SizeFormat::default(),
+            paths: PathDisplay::default(),
             file_count: 0,
             dir_count: 0,
         }
     }
     
+    /// Set maximum file size for line counting
+    pub fn set_max_file_size(&mut self, size: u64) {
+        self.max_file_size = size;
+    }
+    
+    /// Show per-language code statistics for each file
+    pub fn enable_code_stats(&mut self) {
+        self.code_stats = true;
+    }
+    
+    /// Enable or disable colours and set the name colours to use
+    pub fn set_colors(&mut self, enabled: bool, colors: LsColors) {
+        self.color_enabled = enabled;
+        self.colors = colors;
+    }
+    
+    /// Show icons before names
+    pub fn set_icons(&mut self, icons: Option<Icons>) {
+        self.icons = icons;
+    }
+    
+    /// Set the units used for inline sizes
+    pub fn set_size_format(&mut self, size_format: SizeFormat) {
+        self.size_format = size_format;
+    }
+    
+    /// Set how the root and entry paths are shown
+    pub fn set_paths(&mut self, paths: PathDisplay) {
+        self.paths = paths;
+    }
+    
+    /// Create the walker used for reading directories while streaming
+    fn walker(&self, root: &Path) -> Result<Walker> {
+        let mut walker = Walker::new(root, self.filter_opts.clone(), 1)?;
+        walker.set_max_file_size(self.max_file_size);
+        Ok(walker)
+    }
+    
     /// Stream directory tree to stdout
     pub fn stream(&mut self, root: &Path) -> Result<()> {
         match self.format {
@@ -582,7 +1110,7 @@ impl<'a> StreamWalker<'a> {
     /// Stream plain paths (like find)
     fn stream_plain(&mut self, root: &Path) -> Result<()> {
         // Just output full paths, one per line
-        let walker = Walker::new(root, self.filter_opts.clone(), 1)?;
+        let walker = self.walker(root)?;
         self.walk_and_print_plain(&walker, root, 0)?;
         self.stdout.flush()?;
         Ok(())
@@ -591,7 +1119,7 @@ impl<'a> StreamWalker<'a> {
     
     /// Stream tree format
     fn stream_tree(&mut self, root: &Path) -> Result<()> {
-        let walker = Walker::new(root, self.filter_opts.clone(), 1)?;
+        let walker = self.walker(root)?;
         self.walk_and_print_tree(&walker, root, 0, &mut Vec::new())?;
         self.stdout.flush()?;
         
@@ -604,6 +1132,10 @@ impl<'a> StreamWalker<'a> {
             if self.file_count == 1 { "file" } else { "files" }
         );
         
+        if self.filter_opts.search.is_some() {
+            println!("{}", match_summary(self.file_count));
+        }
+        
         Ok(())
     }
     
@@ -617,11 +1149,12 @@ impl<'a> StreamWalker<'a> {
         }
         
         // Print path
-        writeln!(self.stdout, "{}", path.display())?;
+        writeln!(self.stdout, "{}", self.paths.full(path))?;
         
-        // Recurse if directory
-        if path.is_dir() && depth < walker.filter_opts.max_depth.unwrap_or(usize::MAX) {
-            let children = walker.read_directory(path, depth + 1)?;
+        // Recurse if directory, leaving collapsed ones closed
+        let collapsed = depth > 0 && walker.filter_opts.collapses(path);
+        if !collapsed && path.is_dir() && depth < walker.filter_opts.max_depth.unwrap_or(usize::MAX) {
+            let children = walker.visible_children(path, depth + 1)?.into_children();
             for child in children {
                 self.walk_and_print_plain(walker, &child, depth + 1)?;
             }
@@ -647,30 +1180,25 @@ impl<'a> StreamWalker<'a> {
         }
         
         // Print tree line
-        if depth > 0 {
-            // Print prefix
-            for (i, &is_last) in prefix.iter().enumerate() {
-                if i == prefix.len() - 1 {
-                    write!(self.stdout, "{}", if is_last {
-                        if self.unicode { "└── " } else { "`-- " }
-                    } else {
-                        if self.unicode { "├── " } else { "|-- " }
-                    })?;
-                } else {
-                    write!(self.stdout, "{}", if is_last { "    " } else {
-                        if self.unicode { "│   " } else { "|   " }
-                    })?;
-                }
-            }
+        if let Some((&is_last, ancestors)) = prefix.split_last() {
+            write!(self.stdout, "{}", self.tree_chars.graph(ancestors, is_last))?;
         }
         
         // Get metadata for the path
         let metadata = fs::symlink_metadata(path).ok();
-        let is_dir = metadata.as_ref().map_or(false, |m| m.is_dir());
-        let is_symlink = metadata.as_ref().map_or(false, |m| m.is_symlink());
-        let is_executable = metadata.as_ref().map_or(false, |m| is_executable(m));
+        let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
         let size = metadata.as_ref().map_or(0, |m| m.len());
         
+        // The root is never collapsed
+        let collapsed = if is_dir && depth > 0 { walker.collapsed_totals(path) } else { None };
+        
+        // Read children up front, since unopened directories are annotated
+        let listing = if collapsed.is_none() && path.is_dir() && depth < walker.filter_opts.max_depth.unwrap_or(usize::MAX) {
+            Some(walker.visible_children(path, depth + 1)?)
+        } else {
+            None
+        };
+        
         // Update counts
         if is_dir {
             self.dir_count += 1;
@@ -679,45 +1207,67 @@ impl<'a> StreamWalker<'a> {
         }
         
         // Get name
-        let name = if depth == 0 {
-            path.to_string_lossy().to_string()
-        } else {
-            path.file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| path.to_string_lossy().to_string())
-        };
+        let file_name = path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        let name = self.paths.tree_name(&file_name, path, depth);
+        
+        // Pick the icon before the name is consumed by colouring
+        let icon = self.icons.as_ref().map_or_else(String::new, |icons| {
+            let is_symlink = metadata.as_ref().is_some_and(|m| m.is_symlink());
+            let is_executable = metadata.as_ref().is_some_and(is_executable);
+            icons.prefix(&file_name, is_dir, is_symlink, is_executable)
+        });
         
-        // Apply color based on file type
+        // Apply color based on file type, highlighting search matches
         let colored_name = if self.color_enabled {
-            if is_dir {
-                name.blue().bold().to_string()
-            } else if is_symlink {
-                name.cyan().to_string()
-            } else if is_executable {
-                name.green().to_string()
+            // Name matches sit at the end of a full path
+            let offset = name.len().saturating_sub(file_name.len());
+            let highlights = match (&self.filter_opts.search, depth) {
+                (Some(regex), 1..) => name_match_ranges(regex, path, &file_name)
+                    .into_iter()
+                    .map(|range| range.start + offset..range.end + offset)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let classifier = &self.filter_opts.classifier;
+            let color = if classifier.has_colors() && !is_dir {
+                classifier.color(&classifier.classify_name(path))
             } else {
-                name
-            }
+                None
+            };
+            let indicator = Indicator::of(path, metadata.as_ref());
+            paint_name(&name, path, indicator, &self.colors, color, &highlights)
         } else {
             name
         };
         
-        // Build the output line
-        let mut output = colored_name;
+        // Build the output line, starting with the icon
+        let mut output = icon;
+        output.push_str(&colored_name);
         
         // Add size and line count if requested
         if metadata.is_some() {
             let mut details = Vec::new();
             
+            if let Some(totals) = &collapsed {
+                details.push(format_collapsed(totals.files, totals.size, &self.size_format));
+            }
+            
             if self.show_size && !is_dir {
-                details.push(crate::formatter::format_size(size));
+                details.push(self.size_format.format(size));
             }
             
             if self.show_lines && !is_dir && size <= self.max_file_size {
-                if let Ok(lines) = count_lines(path, self.max_file_size) {
-                    if lines > 0 {
-                        details.push(format!("{} lines", lines));
-                    }
+                let lines = walker.line_count(path);
+                if lines > 0 {
+                    details.push(format!("{} lines", lines));
+                }
+            }
+            
+            if self.code_stats && !is_dir {
+                if let Ok(Some(stats)) = count_code(path, self.max_file_size) {
+                    details.push(crate::formatter::format_code_stats(&stats));
                 }
             }
             
@@ -731,11 +1281,15 @@ impl<'a> StreamWalker<'a> {
             }
         }
         
+        if let Some(Listing::OverLimit(count)) = &listing {
+            output.push(' ');
+            output.push_str(&format_unopened(*count));
+        }
+        
         writeln!(self.stdout, "{}", output)?;
         
         // Recurse if directory
-        if path.is_dir() && depth < walker.filter_opts.max_depth.unwrap_or(usize::MAX) {
-            let children = walker.read_directory(path, depth + 1)?;
+        if let Some(Listing::Children(children)) = listing {
             let child_count = children.len();
             
             for (i, child) in children.into_iter().enumerate() {
@@ -770,6 +1324,21 @@ fn load_gitignore(root: &Path) -> Result<Option<Gitignore>> {
     }
 }
 
+/// Serde helper to skip zero counters
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// Serde helper to skip unset flags
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Check if a directory has no entries at all (before any filtering)
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
 /// Convert Path to CString for Unix syscalls
 #[cfg(unix)]
 #[inline(always)]
@@ -845,4 +1414,183 @@ mod tests {
         
         assert!(entries.iter().all(|e| check_max_depth(e, 2)));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_walker_prune_and_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        
+        fs::create_dir_all(root.join("logs/old")).unwrap();
+        fs::create_dir(root.join("blank")).unwrap();
+        fs::write(root.join("logs/old/app.log"), "data").unwrap();
+        fs::write(root.join("notes.txt"), "hello").unwrap();
+        File::create(root.join("zero.txt")).unwrap();
+        
+        // --prune with a filter that leaves logs/old without children
+        let filter_opts = FilterOptions {
+            exclude: Some(regex::Regex::new(r"\.log$").unwrap()),
+            prune: true,
+            ..Default::default()
+        };
+        let mut walker = Walker::new(root, filter_opts, 1).unwrap();
+        let entries = walker.walk().unwrap();
+        let names: Vec<_> = entries[0].children.iter().map(|c| c.name.as_str()).collect();
+        assert!(!names.contains(&"logs"));
+        assert!(!names.contains(&"blank"));
+        assert!(names.contains(&"notes.txt"));
+        
+        // Streaming looks into logs once; logs/old is answered from memory
+        let listing = walker.visible_children(root, 1).unwrap().into_children();
+        assert!(!listing.iter().any(|path| path.ends_with("logs")));
+        assert_eq!(walker.survivors.lock().unwrap().get(&root.join("logs/old")), Some(&false));
+        assert!(!walker.directory_survives(&root.join("logs/old"), 2).unwrap());
+        assert!(walker.survivors.lock().unwrap().is_empty());
+        
+        // --empty keeps zero-byte files and directories with no entries
+        let filter_opts = FilterOptions {
+            empty_only: true,
+            ..Default::default()
+        };
+        let mut walker = Walker::new(root, filter_opts, 1).unwrap();
+        let entries = walker.walk().unwrap();
+        let mut names: Vec<_> = entries[0].children.iter().map(|c| c.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["blank", "zero.txt"]);
+    }
+    
+    #[test]
+    fn test_walker_sort_by_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        
+        // Byte size and line count deliberately disagree
+        fs::write(root.join("few.txt"), "one very long line of text\n").unwrap();
+        fs::write(root.join("many.txt"), "a\nb\nc\nd\n").unwrap();
+        fs::write(root.join("some.txt"), "a\nb\n").unwrap();
+        
+        let filter_opts = FilterOptions {
+            sort_keys: vec![crate::SortKey { field: SortBy::Lines, descending: true }],
+            ..Default::default()
+        };
+        let mut walker = Walker::new(root, filter_opts, 1).unwrap();
+        walker.enable_line_counting();
+        let entries = walker.walk().unwrap();
+        
+        let names: Vec<_> = entries[0].children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["many.txt", "some.txt", "few.txt"]);
+        assert_eq!(entries[0].children[0].line_count, 4);
+        
+        // Every file was counted exactly once and cached
+        assert_eq!(walker.line_counts.lock().unwrap().len(), 3);
+        
+        // Without a line sort nothing is cached
+        let mut walker = Walker::new(root, FilterOptions::default(), 1).unwrap();
+        walker.enable_line_counting();
+        let entries = walker.walk().unwrap();
+        assert_eq!(entries[0].children.iter().map(|c| c.line_count).sum::<u64>(), 7);
+        assert!(walker.line_counts.lock().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_walker_collapse() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        
+        fs::create_dir_all(root.join("node_modules/pkg/lib")).unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "module.exports = 1;\n").unwrap();
+        fs::write(root.join("node_modules/pkg/lib/util.js"), "x\n").unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/main.js"), "run();\n").unwrap();
+        
+        let mut builder = globset::GlobSetBuilder::new();
+        builder.add(globset::Glob::new("node_modules").unwrap());
+        let filter_opts = FilterOptions {
+            collapse: Some(builder.build().unwrap()),
+            ..Default::default()
+        };
+        let mut walker = Walker::new(root, filter_opts, 1).unwrap();
+        let entries = walker.walk().unwrap();
+        
+        let modules = entries[0].children.iter().find(|c| c.name == "node_modules").unwrap();
+        assert!(modules.collapsed);
+        assert!(modules.children.is_empty());
+        assert_eq!(modules.file_count, 2);
+        assert_eq!(modules.size, 22);
+        
+        let src = entries[0].children.iter().find(|c| c.name == "src").unwrap();
+        assert!(!src.collapsed);
+        assert_eq!(src.children.len(), 1);
+    }
+    
+    #[test]
+    fn test_walker_largest() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        
+        fs::create_dir_all(root.join("data/raw")).unwrap();
+        fs::write(root.join("data/raw/dump.bin"), vec![0u8; 400]).unwrap();
+        fs::write(root.join("data/small.csv"), vec![0u8; 50]).unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs/guide.md"), vec![0u8; 100]).unwrap();
+        fs::write(root.join(".cache"), vec![0u8; 1000]).unwrap();
+        
+        let walker = Walker::new(root, FilterOptions::default(), 1).unwrap();
+        let largest = walker.largest(2, 2);
+        
+        // Hidden files are filtered out as in the tree
+        assert_eq!(largest.total_size, 550);
+        let files: Vec<_> = largest.files.iter().map(|item| item.path.file_name().unwrap()).collect();
+        assert_eq!(files, vec!["dump.bin", "guide.md"]);
+        
+        let dirs: Vec<_> = largest.dirs.iter().map(|item| (item.path.file_name().unwrap(), item.size)).collect();
+        assert_eq!(dirs, vec![(OsStr::new("data"), 450), (OsStr::new("raw"), 400)]);
+        
+        // Ranking the walked tree gives the same report without another scan
+        let mut walker = walker;
+        let entries = walker.walk().unwrap();
+        let from_tree = Largest::from_entries(&entries, 2, 2);
+        assert_eq!(from_tree.total_size, largest.total_size);
+        assert_eq!(from_tree.files, largest.files);
+        assert_eq!(from_tree.dirs, largest.dirs);
+    }
+    
+    #[test]
+    fn test_walker_file_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        
+        fs::create_dir(root.join("big")).unwrap();
+        for i in 0..4 {
+            File::create(root.join(format!("big/{}.txt", i))).unwrap();
+        }
+        File::create(root.join("big/.hidden")).unwrap();
+        fs::create_dir(root.join("small")).unwrap();
+        File::create(root.join("small/one.txt")).unwrap();
+        
+        let filter_opts = FilterOptions {
+            file_limit: Some(3),
+            ..Default::default()
+        };
+        let mut walker = Walker::new(root, filter_opts, 1).unwrap();
+        let entries = walker.walk().unwrap();
+        
+        let big = entries[0].children.iter().find(|c| c.name == "big").unwrap();
+        assert_eq!(big.unopened_entries, Some(4));
+        assert!(big.children.is_empty());
+        
+        let small = entries[0].children.iter().find(|c| c.name == "small").unwrap();
+        assert_eq!(small.unopened_entries, None);
+        assert_eq!(small.children.len(), 1);
+        
+        // Hidden entries count once they are shown
+        let filter_opts = FilterOptions {
+            file_limit: Some(3),
+            show_hidden: true,
+            ..Default::default()
+        };
+        let mut walker = Walker::new(root, filter_opts, 1).unwrap();
+        let entries = walker.walk().unwrap();
+        let big = entries[0].children.iter().find(|c| c.name == "big").unwrap();
+        assert_eq!(big.unopened_entries, Some(5));
+    }
+}
@@ -157,4 +157,1055 @@ fn test_gitignore() {
         .assert()
         .success()
         .stdout(predicate::str::contains("test.log").not());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_prune_streaming() {
+    let temp_dir = create_test_tree();
+    fs::create_dir_all(temp_dir.path().join("build/cache")).unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--prune")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("build").not())
+        .stdout(predicate::str::contains("cache").not())
+        .stdout(predicate::str::contains("main.rs"));
+}
+
+#[test]
+fn test_empty() {
+    let temp_dir = create_test_tree();
+    File::create(temp_dir.path().join("src/empty.rs")).unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--empty")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("empty.rs"))
+        .stdout(predicate::str::contains("docs"))
+        .stdout(predicate::str::contains("main.rs").not())
+        .stdout(predicate::str::contains("tests").not());
+}
+
+#[test]
+fn test_contains() {
+    let temp_dir = create_test_tree();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--contains=println")
+        .arg("--first-match")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("1 match"))
+        .stdout(predicate::str::contains("2: println!"))
+        .stdout(predicate::str::contains("lib.rs").not())
+        .stdout(predicate::str::contains("tests").not());
+    
+    // JSON output carries line numbers
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--contains=println")
+        .arg("--output=json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"matches\""))
+        .stdout(predicate::str::contains("\"line\": 2"));
+}
+
+#[test]
+fn test_search_keeps_only_matching_directories() {
+    let temp_dir = create_test_tree();
+    
+    // Streaming path
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--search=main")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("src"))
+        .stdout(predicate::str::contains("docs").not())
+        .stdout(predicate::str::contains("tests").not())
+        .stdout(predicate::str::contains("1 match"));
+    
+    // Buffered path
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--search=main")
+        .arg("--sort=name")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("docs").not())
+        .stdout(predicate::str::contains("1 match"));
+}
+
+#[test]
+fn test_fuzzy_plain_ranked() {
+    let temp_dir = create_test_tree();
+    
+    let output = Command::cargo_bin("maram").unwrap()
+        .arg(temp_dir.path())
+        .arg("--fuzzy=mrs")
+        .arg("--output=plain")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].ends_with("main.rs"));
+    assert!(!stdout.contains("Cargo.toml"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--fuzzy=mrs")
+        .arg("--fuzzy-limit=1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("tests").not());
+}
+
+#[test]
+fn test_natural_sort_dirs_first() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    for name in ["file10.txt", "file2.txt", "File1.txt"] {
+        File::create(root.join(name)).unwrap();
+    }
+    fs::create_dir(root.join("zeta")).unwrap();
+    
+    let output = Command::cargo_bin("maram").unwrap()
+        .arg(root)
+        .arg("--sort=natural")
+        .arg("--ignore-case-sort")
+        .arg("--dirs-first")
+        .arg("--output=plain")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let names: Vec<&str> = stdout.lines().skip(1).map(|l| l.rsplit('/').next().unwrap()).collect();
+    assert_eq!(names, vec!["zeta", "File1.txt", "file2.txt", "file10.txt"]);
+}
+
+#[test]
+fn test_sort_by_recursive_dir_size() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("light")).unwrap();
+    fs::create_dir_all(root.join("heavy/nested")).unwrap();
+    fs::write(root.join("light/a.txt"), "x").unwrap();
+    fs::write(root.join("heavy/nested/big.bin"), "x".repeat(10_000)).unwrap();
+    fs::write(root.join("medium.txt"), "x".repeat(100)).unwrap();
+    // Hidden files only weigh in when they are shown
+    fs::write(root.join("light/.cache"), "x".repeat(50_000)).unwrap();
+    
+    let sorted_by_size = |extra: &[&str]| -> Vec<String> {
+        let output = Command::cargo_bin("maram").unwrap()
+            .arg(root)
+            .arg("--sort=size")
+            .arg("-r")
+            .arg("--depth=1")
+            .arg("--output=plain")
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout.lines().skip(1)
+            .map(|l| l.rsplit('/').next().unwrap().to_string())
+            .filter(|name| !name.starts_with('.'))
+            .collect()
+    };
+    assert_eq!(sorted_by_size(&[]), vec!["heavy", "medium.txt", "light"]);
+    assert_eq!(sorted_by_size(&["--all"]), vec!["light", "heavy", "medium.txt"]);
+    // Directory-only listings still rank directories by their files
+    assert_eq!(sorted_by_size(&["--only-dirs"]), vec!["heavy", "light"]);
+    
+    // Multi-key sort with per-key direction
+    let output = Command::cargo_bin("maram").unwrap()
+        .arg(root)
+        .arg("--sort=files:desc,name")
+        .arg("--depth=1")
+        .arg("--output=plain")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let names: Vec<&str> = stdout.lines().skip(1).map(|l| l.rsplit('/').next().unwrap()).collect();
+    assert_eq!(names, vec!["heavy", "light", "medium.txt"]);
+}
+
+#[test]
+fn test_code_stats_and_language_distribution() {
+    let temp_dir = create_test_tree();
+    let mut file = File::create(temp_dir.path().join("src/util.rs")).unwrap();
+    writeln!(file, "// helper\n\nfn helper() {{}}").unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--code-stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rust: 1 code, 1 comments, 1 blank"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--dist=language")
+        .arg("--format=table")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Comments"))
+        .stdout(predicate::str::is_match(r"Rust\s+4\s+11\s+1\s+1").unwrap());
+    
+    // An explicit metric replaces the language table
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--dist=language")
+        .arg("--dist-metric=files")
+        .arg("--format=table")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Comments").not())
+        .stdout(predicate::str::is_match(r"Rust\s+4\s+\d+\.\d%").unwrap());
+}
+
+#[test]
+fn test_file_kind_classification() {
+    let temp_dir = create_test_tree();
+    fs::write(temp_dir.path().join("program"), b"\x7FELF\x02\x01\x01\0\0\0\0\0").unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--output=json")
+        .arg("--file-kinds")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""kind": "Executables""#))
+        .stdout(predicate::str::contains(r#""mime": "application/toml""#));
+    
+    // Plain JSON listings don't sniff file headers
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--output=json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""kind""#).not());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--dist=type")
+        .arg("--format=table")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Executables"))
+        .stdout(predicate::str::contains("Config"));
+}
+
+#[test]
+fn test_category_filter_with_config_categories() {
+    let temp_dir = create_test_tree();
+    let home = TempDir::new().unwrap();
+    fs::write(
+        home.path().join(".maram.toml"),
+        "[categories.Docs]\nextensions = [\"md\"]\nglobs = [\"LICENSE*\"]\ncolor = \"magenta\"\n",
+    ).unwrap();
+    File::create(temp_dir.path().join("LICENSE-MIT")).unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.env("HOME", home.path())
+        .arg(temp_dir.path())
+        .arg("--category=code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("test.rs"))
+        .stdout(predicate::str::contains("README.md").not())
+        .stdout(predicate::str::contains("docs").not());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.env("HOME", home.path())
+        .arg(temp_dir.path())
+        .arg("--category=Docs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("README.md"))
+        .stdout(predicate::str::contains("LICENSE-MIT"))
+        .stdout(predicate::str::contains("main.rs").not());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.env("HOME", home.path())
+        .arg(temp_dir.path())
+        .arg("--dist=type")
+        .arg("--format=table")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Docs"));
+}
+
+#[test]
+fn test_category_globs_relative_to_root() {
+    let temp_dir = create_test_tree();
+    let home = TempDir::new().unwrap();
+    fs::write(
+        home.path().join(".maram.toml"),
+        "[categories.Docs]\nglobs = [\"docs/**\"]\n",
+    ).unwrap();
+    File::create(temp_dir.path().join("docs/guide.rs")).unwrap();
+    
+    // Streaming and buffered walks both match from the walk root
+    for extra in [None, Some("--sort=name")] {
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.env("HOME", home.path())
+            .arg(temp_dir.path())
+            .arg("--category=Docs")
+            .args(extra)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("guide.rs"))
+            .stdout(predicate::str::contains("main.rs").not());
+    }
+}
+
+#[test]
+fn test_ls_colors() {
+    let temp_dir = create_test_tree();
+    
+    // Streaming and buffered output colour names the same way
+    for extra in [None, Some("--sort=name")] {
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.env("LS_COLORS", "di=01;36:*.md=04;33")
+            .arg(temp_dir.path())
+            .arg("--color");
+        if let Some(arg) = extra {
+            cmd.arg(arg);
+        }
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("\x1b[04;33mREADME.md\x1b[0m"))
+            .stdout(predicate::str::contains("\x1b[01;36msrc\x1b[0m"));
+    }
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.env("LS_COLORS", "*.md=04;33")
+        .arg(temp_dir.path())
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_search_highlight_skips_root() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("data");
+    fs::create_dir(&root).unwrap();
+    fs::write(root.join("data.txt"), "x").unwrap();
+    
+    // Streaming and buffered output highlight the same names
+    let outputs: Vec<String> = [None, Some("--sort=name")].into_iter()
+        .map(|extra| {
+            let output = Command::cargo_bin("maram").unwrap()
+                .env("LS_COLORS", "")
+                .arg(&root)
+                .arg("--search=data")
+                .arg("--color")
+                .args(extra)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        })
+        .collect();
+    assert_eq!(outputs[0], outputs[1]);
+    assert!(!outputs[0].lines().next().unwrap().contains("91m"));
+    assert!(outputs[0].contains("91mdata"));
+}
+
+#[test]
+fn test_icons() {
+    let temp_dir = create_test_tree();
+    
+    for extra in [None, Some("--sort=name")] {
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.arg(temp_dir.path()).arg("--icons");
+        if let Some(arg) = extra {
+            cmd.arg(arg);
+        }
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("\u{e7a8} Cargo.toml"))
+            .stdout(predicate::str::contains("\u{f121} src"))
+            .stdout(predicate::str::contains("\u{f405} README.md"));
+    }
+}
+
+#[test]
+fn test_long_listing_aligns_columns() {
+    let temp_dir = create_test_tree();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    let output = cmd.arg(temp_dir.path())
+        .arg("--long")
+        .arg("--show-lines")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    
+    let date = regex::Regex::new(r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}").unwrap();
+    let rows: Vec<&str> = stdout.lines().filter(|line| date.is_match(line)).collect();
+    assert_eq!(rows.len(), 9);
+    
+    // Every row starts its tree graph at the same column
+    let graph_start: Vec<usize> = rows.iter()
+        .map(|row| date.find(row).unwrap().end())
+        .collect();
+    assert!(graph_start.iter().all(|&col| col == graph_start[0]));
+    
+    let main_rs = rows.iter().find(|row| row.ends_with("main.rs")).unwrap();
+    assert!(regex::Regex::new(r"^-rw\S+\s+\S+\s+\d+ B\s+3\s+\d{4}-").unwrap().is_match(main_rs));
+    // Size and lines move into columns instead of trailing details
+    assert!(!stdout.contains("lines)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_permissions_owner_group_columns() {
+    let temp_dir = create_test_tree();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("-pug")
+        .arg("--inodes")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"\[\d+ -rw\S{7} \S+ +\S+ *\] main\.rs").unwrap());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("-p")
+        .arg("--output=csv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("modified,mode,user,group,uid,gid,inode,device,nlink"))
+        .stdout(predicate::str::is_match(r"src/main\.rs,file,\d+,0,\d+,-rw\S{7},").unwrap());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--inodes")
+        .arg("--output=json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""inode":"#))
+        .stdout(predicate::str::contains(r#""nlink": 1"#));
+}
+
+#[test]
+fn test_date_and_time_styles() {
+    let temp_dir = create_test_tree();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("-D")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"\[\d{4}-\d{2}-\d{2} \d{2}:\d{2}\] main\.rs").unwrap());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("-D")
+        .arg("--time-style=relative")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"\[(just now|\d+ seconds? ago)\s*\] main\.rs").unwrap());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--output=csv")
+        .arg("--time-style=+%Y/%m/%d, %H")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r#"src/main\.rs,file,\d+,0,"\d{4}/\d{2}/\d{2}, \d{2}""#).unwrap());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--output=json")
+        .arg("--time-style=iso")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r#""modified": "\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}[+-]\d{2}:\d{2}""#).unwrap());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--time-style=fancy")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown time style"));
+}
+
+#[test]
+fn test_size_units() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("data.bin"), vec![0u8; 1536]).unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("data.bin (1.5 KiB)"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--units=si")
+        .arg("--precision=2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("data.bin (1.54 kB)"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--units=bytes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("data.bin (1536 B)"));
+    
+    // 1536 bytes is above 1.5kB but not above 1.5KiB
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--min-size=1.5kB")
+        .arg("--output=csv")
+        .arg("--human-sizes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/data.bin,file,1.5 KiB,"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--min-size=1.6KiB")
+        .arg("--output=csv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("data.bin").not());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--output=json")
+        .arg("--human-sizes")
+        .arg("--units=KiB")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""size": "1.5 KiB""#));
+}
+
+#[test]
+fn test_full_path_modes() {
+    let temp_dir = create_test_tree();
+    let root = temp_dir.path();
+    let canonical = root.canonicalize().unwrap();
+    
+    // Streaming and buffered trees name entries the same way
+    for sort in [None, Some("--sort=name")] {
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.arg(root).arg("-f").arg("--root-label=proj");
+        if let Some(sort) = sort {
+            cmd.arg(sort);
+        }
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("proj\n"))
+            .stdout(predicate::str::contains("── proj/src/main.rs"));
+    }
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--absolute")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("── {}", canonical.join("src").display())));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--root-label=proj")
+        .arg("--output=plain")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("proj/src/main.rs\n"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--root-label=proj")
+        .arg("--output=csv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\nproj,directory,"))
+        .stdout(predicate::str::contains("\nproj/src/main.rs,file,"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--absolute")
+        .arg("--output=json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(r#""path": "{}""#, canonical.join("src/main.rs").display())));
+}
+
+#[test]
+fn test_charsets_match_between_streaming_and_buffered() {
+    // One directory and one file per level, so both walkers list entries in the same order
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/nested")).unwrap();
+    fs::write(root.join("README.md"), "readme\n").unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("src/nested/deep.txt"), "deep\n").unwrap();
+    
+    let run = |extra: &[&str]| {
+        let output = Command::cargo_bin("maram").unwrap()
+            .arg(root)
+            .arg("--dirs-first")
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    
+    for charset in ["ascii", "unicode", "rounded", "heavy", "double", "compact"] {
+        for options in [&[][..], &["-f", "--icons"][..], &["--color", "--show-lines"][..]] {
+            let mut args = vec![format!("--charset={}", charset)];
+            args.extend(options.iter().map(|option| option.to_string()));
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            
+            // Sorting forces the buffered printer
+            let streamed = run(&args);
+            let buffered = run(&[&args[..], &["--sort=name"]].concat());
+            assert_eq!(streamed, buffered, "charset {} with {:?}", charset, options);
+        }
+    }
+    
+    let ascii = run(&["--charset=ascii"]);
+    assert!(ascii.contains("|-- src\n|   |-- nested\n|   |   `-- deep.txt"));
+    let compact = run(&["--charset=compact", "--sort=name"]);
+    assert!(compact.contains("├ src\n│ ├ nested\n│ │ └ deep.txt"));
+    let rounded = run(&["--charset=rounded"]);
+    assert!(rounded.contains("╰── README.md"));
+}
+
+#[test]
+fn test_compact_dirs() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/main/java/com/acme")).unwrap();
+    fs::create_dir_all(root.join("src/test")).unwrap();
+    fs::write(root.join("src/main/java/com/acme/App.java"), "class App {}\n").unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--compact-dirs")
+        .arg("--dir-sizes")
+        .arg("--charset=ascii")
+        .arg("--sort=name")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("`-- src (13 B)\n    |-- main/java/com/acme/ (13 B)\n    |   `-- App.java (13 B)\n    `-- test (0 B)"))
+        // Collapsed directories still count
+        .stdout(predicate::str::contains("7 directories, 1 file"));
+    
+    // Plain output lists whole paths, so there are no chains to compact
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--compact-dirs")
+        .arg("--output=plain")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/src/main/java/com/acme/App.java\n"));
+}
+
+#[test]
+fn test_plain_output_matches_streaming() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/a")).unwrap();
+    fs::write(root.join("src/a/b.txt"), "b").unwrap();
+    
+    // --sort=name switches to the buffered printer
+    let plain = |extra: &[&str]| {
+        let output = Command::cargo_bin("maram").unwrap()
+            .arg(root)
+            .arg("--output=plain")
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let streamed = plain(&[]);
+    assert_eq!(streamed.lines().last().unwrap(), format!("{}/src/a/b.txt", root.display()));
+    assert_eq!(plain(&["--sort=name"]), streamed);
+    assert_eq!(plain(&["-f", "--sort=name"]), plain(&["-f"]));
+}
+
+#[test]
+fn test_collapse() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("node_modules/left-pad")).unwrap();
+    fs::write(root.join("node_modules/left-pad/index.js"), "module.exports = pad;\n").unwrap();
+    fs::write(root.join("node_modules/.package-lock.json"), "{}\n").unwrap();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/app.js"), "pad();\n").unwrap();
+    
+    // Streaming and buffered output agree, and hidden files only count
+    // when they are shown
+    for extra in [None, Some("--sort=name")] {
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.arg(root).arg("--collapse").arg("--charset=ascii");
+        if let Some(arg) = extra {
+            cmd.arg(arg);
+        }
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("node_modules (1 file, 22 B)\n"))
+            .stdout(predicate::str::contains("left-pad").not())
+            .stdout(predicate::str::contains("app.js (7 B)"));
+        
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.arg(root).arg("--collapse").arg("--charset=ascii").arg("--all");
+        if let Some(arg) = extra {
+            cmd.arg(arg);
+        }
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("node_modules (2 files, 25 B)\n"));
+    }
+    
+    // Explicit patterns replace the built-in list
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--collapse=sr*")
+        .arg("--output=plain")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("left-pad/index.js"))
+        .stdout(predicate::str::contains("app.js").not());
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--collapse")
+        .arg("--output=json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"collapsed\": true"))
+        .stdout(predicate::str::contains("\"file_count\": 1"));
+}
+
+#[test]
+fn test_filelimit() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join("logs")).unwrap();
+    for i in 0..5 {
+        fs::write(root.join(format!("logs/{}.log", i)), "x").unwrap();
+    }
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    
+    // Streaming and buffered output agree
+    for extra in [None, Some("--sort=name")] {
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.arg(root).arg("--filelimit=4");
+        if let Some(arg) = extra {
+            cmd.arg(arg);
+        }
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("logs [5 entries exceeds filelimit, not opened]\n"))
+            .stdout(predicate::str::contains("0.log").not())
+            .stdout(predicate::str::contains("main.rs"));
+    }
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--filelimit=4")
+        .arg("--output=json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"unopened_entries\": 5"));
+}
+
+#[test]
+fn test_fit() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("assets/icons")).unwrap();
+    for i in 0..6 {
+        fs::write(root.join(format!("assets/icons/{}.svg", i)), "<svg/>").unwrap();
+    }
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    
+    // Root, two directories and the one child of assets
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--fit=4")
+        .arg("--charset=ascii")
+        .arg("--sort=name")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("|   `-- icons (6 files, 36 B)\n"))
+        .stdout(predicate::str::contains("`-- src (1 file, 13 B)\n"))
+        // The summary still counts the whole tree
+        .stdout(predicate::str::contains("4 directories, 7 files"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--fit=20")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("5.svg"))
+        .stdout(predicate::str::contains("files,").not());
+    
+    // Other outputs would silently ignore it
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--fit=4")
+        .arg("--output=plain")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--output=tree"));
+}
+
+#[test]
+fn test_fit_with_collapse() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("web/node_modules")).unwrap();
+    fs::write(root.join("web/index.js"), "//").unwrap();
+    for i in 0..5 {
+        fs::write(root.join(format!("web/node_modules/{}.js", i)), vec![b'x'; 10]).unwrap();
+    }
+    
+    // The collapsed node_modules still counts towards web's summary
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--collapse")
+        .arg("--fit=3")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web (6 files, 52 B)"));
+}
+
+#[test]
+fn test_largest() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join("media")).unwrap();
+    fs::write(root.join("media/video.mp4"), vec![0u8; 3000]).unwrap();
+    fs::write(root.join("media/photo.jpg"), vec![0u8; 500]).unwrap();
+    fs::write(root.join("notes.txt"), vec![0u8; 500]).unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--largest=1")
+        .arg("--largest-dirs=1")
+        .assert()
+        .success()
+        // The tree comes first
+        .stdout(predicate::str::contains("notes.txt (500 B)"))
+        .stdout(predicate::str::contains("Largest files\n2.9 KiB   75.0%  "))
+        .stdout(predicate::str::contains("media/video.mp4\n"))
+        .stdout(predicate::str::contains("Largest directories\n3.4 KiB   87.5%  "));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    let output = cmd.arg(root)
+        .arg("--largest=2")
+        .arg("--no-tree")
+        .arg("--output=json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let report = &document["largest"];
+    assert_eq!(report["total_size"], 4000);
+    assert_eq!(report["files"][0]["size"], 3000);
+    assert_eq!(report["files"][0]["share"], 75.0);
+    assert_eq!(report["files"].as_array().unwrap().len(), 2);
+    assert!(report["dirs"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_distribution_dimensions() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/bin")).unwrap();
+    fs::write(root.join("src/lib.rs"), "a\nb\nc\n").unwrap();
+    fs::write(root.join("src/bin/main.rs"), "a\n").unwrap();
+    fs::create_dir(root.join("docs")).unwrap();
+    fs::write(root.join("docs/guide.md"), "a\nb\nc\nd\n").unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--dist=dir")
+        .arg("--dist-metric=files")
+        .arg("--format=table")
+        .arg("--no-tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Files  Percent"))
+        .stdout(predicate::str::contains("src/            2    66.7%"))
+        .stdout(predicate::str::contains("docs/            1    33.3%"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--dist=depth")
+        .arg("--dist-metric=lines")
+        .arg("--format=table")
+        .arg("--no-tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("depth 2            7    87.5%"))
+        .stdout(predicate::str::contains("depth 3            1    12.5%"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--dist=age")
+        .arg("--no-tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Size Distribution"))
+        .stdout(predicate::str::contains("< 1d 100.0%"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--dist=owner")
+        .arg("--format=table")
+        .arg("--no-tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unknown").not());
+}
+
+#[test]
+fn test_json_document() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), vec![b'x'; 300]).unwrap();
+    fs::write(root.join("README.md"), vec![b'x'; 100]).unwrap();
+    
+    // Distribution, totals and summary share one document with the tree
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    let output = cmd.arg(root)
+        .arg("--output=json")
+        .arg("--dist=ext")
+        .arg("--largest=1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["tree"][0]["children"].as_array().unwrap().len(), 2);
+    assert_eq!(document["summary"]["directories"], 2);
+    assert_eq!(document["summary"]["files"], 2);
+    assert_eq!(document["totals"]["file_size"], 400);
+    assert_eq!(document["distribution"]["by"], "ext");
+    assert_eq!(document["distribution"]["metric"], "bytes");
+    assert_eq!(document["distribution"]["items"][0]["category"], "rs");
+    assert_eq!(document["distribution"]["items"][0]["share"], 75.0);
+    assert_eq!(document["largest"]["files"][0]["size"], 300);
+    
+    // Without the tree the document still has the summary and totals
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    let output = cmd.arg(root)
+        .arg("--output=json")
+        .arg("--no-tree")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(document.get("tree").is_none());
+    assert_eq!(document["summary"]["files"], 2);
+    assert_eq!(document["totals"]["file_size"], 400);
+}
+
+#[test]
+fn test_distribution_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(root.join("a.rs"), vec![b'x'; 300]).unwrap();
+    fs::write(root.join("b.md"), vec![b'x'; 100]).unwrap();
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--dist=ext")
+        .arg("--dist-output=csv")
+        .arg("--no-tree")
+        .assert()
+        .success()
+        .stdout("category,value,share\nrs,300,75.00\nmd,100,25.00\n");
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    let output = cmd.arg(root)
+        .arg("--dist=ext")
+        .arg("--dist-metric=files")
+        .arg("--dist-output=json")
+        .arg("--no-tree")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let distribution: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(distribution["metric"], "files");
+    assert_eq!(distribution["total"], 2);
+    assert_eq!(distribution["items"].as_array().unwrap().len(), 2);
+    
+    // Languages keep the columns of the language table
+    fs::write(root.join("c.rs"), "// note\nfn main() {}\n\n").unwrap();
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    cmd.arg(root)
+        .arg("--dist=language")
+        .arg("--dist-output=csv")
+        .arg("--no-tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("language,files,code,comments,blank\nRust,2,"));
+    
+    let mut cmd = Command::cargo_bin("maram").unwrap();
+    let output = cmd.arg(root)
+        .arg("--dist=language")
+        .arg("--dist-output=json")
+        .arg("--no-tree")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let distribution: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(distribution["by"], "language");
+    assert_eq!(distribution["items"][0]["language"], "Rust");
+    assert_eq!(distribution["items"][0]["comments"], 1);
+    assert_eq!(distribution["total"]["files"], 3);
+    
+    // A structured distribution is never printed next to another document
+    for extra in [
+        vec!["--dist-output=json"],
+        vec!["--dist-output=csv", "--output=csv"],
+        vec!["--dist-output=csv", "--output=json"],
+        vec!["--dist-output=json", "--no-tree", "--largest=1"],
+    ] {
+        let mut cmd = Command::cargo_bin("maram").unwrap();
+        cmd.arg(root)
+            .arg("--dist=ext")
+            .args(&extra)
+            .assert()
+            .failure();
+    }
+}